@@ -0,0 +1,16 @@
+-- migrations/*_auth_events_table.up.sql
+-- Add up migration script here
+-- Журнал событий аутентификации (аудит безопасности)
+CREATE TABLE IF NOT EXISTS auth_events(
+    id UUID NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NULL,
+    event_type TEXT NOT NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('success', 'failure')),
+    ip_address TEXT NULL,
+    user_agent TEXT NULL,
+    details JSONB NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+    );
+CREATE INDEX IF NOT EXISTS auth_events_user_id_created_at_indx ON auth_events (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS auth_events_event_type_created_at_indx ON auth_events (event_type, created_at DESC);
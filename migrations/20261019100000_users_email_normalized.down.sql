@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+DROP INDEX IF EXISTS users_email_normalized_key;
+ALTER TABLE users DROP COLUMN IF EXISTS email_normalized;
@@ -0,0 +1,11 @@
+-- migrations/*_users_email_normalized.up.sql
+-- Add up migration script here
+-- Нормализованный email — ключ учётной записи, сравнение без учёта регистра.
+-- Существующие записи заполняются адресом в нижнем регистре без пробелов.
+-- Если в таблице уже есть адреса, отличающиеся только регистром, создание индекса
+-- завершится ошибкой: такие учётные записи нужно объединить вручную до миграции.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS email_normalized TEXT NULL;
+UPDATE users SET email_normalized = lower(trim(email)) WHERE email_normalized IS NULL;
+ALTER TABLE users ALTER COLUMN email_normalized SET NOT NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_normalized_key ON users (email_normalized);
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
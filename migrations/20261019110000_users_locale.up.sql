@@ -0,0 +1,4 @@
+-- Add up migration script here
+-- Язык писем и ответов API, выбранный пользователем (`en`, `ru`).
+-- NULL — язык не выбран, используется `Accept-Language` запроса.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locale TEXT NULL;
@@ -0,0 +1,438 @@
+//! Административные команды, которые раньше выполнялись вручную через psql.
+//! Настройки и подключения берутся так же, как у основного приложения
+//! (`APP_ENVIRONMENT`, файлы в `settings/`, переменные `APP_*`).
+//!
+//! ```
+//! cargo run --bin backend-admin -- migrate
+//! cargo run --bin backend-admin -- create-superuser --email admin@example.com
+//! cargo run --bin backend-admin -- list-users --inactive
+//! cargo run --bin backend-admin -- normalize-emails --dry-run
+//! ```
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use backend::i18n::Locale;
+use backend::settings::{get_settings, Settings};
+use backend::startup::{get_connection_pool, get_redis_pool, MIGRATOR};
+use backend::utils::{
+    hash, normalize_email, renormalize_emails, send_multipart_email, validate_password,
+    BackgroundTasks, EmailRecipient,
+};
+use chrono::{DateTime, Utc};
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::io::{self, BufRead, Write};
+
+type AdminResult = Result<(), Box<dyn std::error::Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "backend-admin",
+    about = "Operational tasks for the auth backend"
+)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Применить все новые миграции.
+    Migrate,
+    /// Откатить миграции с версией больше `--target`; по умолчанию — последнюю применённую.
+    Revert {
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// Создать активного суперпользователя; недостающие данные запрашиваются интерактивно.
+    CreateSuperuser {
+        #[arg(long)]
+        email: Option<String>,
+        #[arg(long)]
+        first_name: Option<String>,
+        #[arg(long)]
+        last_name: Option<String>,
+    },
+    /// Активировать учётную запись.
+    Activate { email: String },
+    /// Деактивировать учётную запись.
+    Deactivate { email: String },
+    /// Задать новый пароль; он проверяется по политике паролей из настроек.
+    ResetPassword { email: String },
+    /// Вывести список пользователей, новые сверху.
+    ListUsers {
+        #[arg(long, conflicts_with = "inactive")]
+        active: bool,
+        #[arg(long)]
+        inactive: bool,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Сгенерировать `secret_key` и `hmac_secret` для файла настроек.
+    GenerateSecrets,
+    /// Удалить неактивированные учётные записи, ссылка подтверждения которых уже истекла.
+    PurgeUnactivated {
+        /// Возраст учётной записи в часах; по умолчанию — срок жизни токена подтверждения.
+        #[arg(long)]
+        older_than_hours: Option<i64>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Повторно отправить письмо с подтверждением неактивированному пользователю.
+    ResendVerification { email: String },
+    /// Пересчитать ключи `email_normalized` по правилам `email_normalization` из настроек.
+    /// Обязательный шаг после изменения этих правил: приложение само ключи не пересчитывает.
+    NormalizeEmails {
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> AdminResult {
+    dotenv().ok();
+    let args = Args::parse();
+
+    // Секреты генерируются без подключения к БД и даже без файла настроек.
+    if let Command::GenerateSecrets = args.command {
+        generate_secrets();
+        return Ok(());
+    }
+
+    let settings = get_settings()?;
+    let pool = get_connection_pool(&settings.database).await;
+
+    match args.command {
+        Command::Migrate => {
+            MIGRATOR.run(&pool).await?;
+            println!("Migrations applied");
+        }
+        Command::Revert { target } => revert(&pool, target).await?,
+        Command::CreateSuperuser {
+            email,
+            first_name,
+            last_name,
+        } => create_superuser(&pool, &settings, email, first_name, last_name).await?,
+        Command::Activate { email } => set_active(&pool, &settings, &email, true).await?,
+        Command::Deactivate { email } => set_active(&pool, &settings, &email, false).await?,
+        Command::ResetPassword { email } => reset_password(&pool, &settings, &email).await?,
+        Command::ListUsers {
+            active,
+            inactive,
+            limit,
+        } => {
+            let is_active = match (active, inactive) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            list_users(&pool, is_active, limit).await?
+        }
+        Command::PurgeUnactivated {
+            older_than_hours,
+            dry_run,
+        } => purge_unactivated(&pool, &settings, older_than_hours, dry_run).await?,
+        Command::ResendVerification { email } => {
+            resend_verification(&pool, &settings, &email).await?
+        }
+        Command::NormalizeEmails { dry_run } => normalize_emails(&pool, &settings, dry_run).await?,
+        Command::GenerateSecrets => unreachable!(),
+    }
+
+    Ok(())
+}
+
+async fn revert(pool: &PgPool, target: Option<i64>) -> AdminResult {
+    let applied: Vec<i64> =
+        sqlx::query("SELECT version FROM _sqlx_migrations ORDER BY version DESC LIMIT 2")
+            .map(|row: PgRow| row.get("version"))
+            .fetch_all(pool)
+            .await?;
+
+    if applied.is_empty() {
+        println!("No migrations to revert");
+        return Ok(());
+    }
+
+    // Без `--target` откатывается только последняя миграция.
+    let target = target.unwrap_or_else(|| applied.get(1).copied().unwrap_or(0));
+    MIGRATOR.undo(pool, target).await?;
+    println!("Reverted migrations newer than {}", target);
+    Ok(())
+}
+
+async fn create_superuser(
+    pool: &PgPool,
+    settings: &Settings,
+    email: Option<String>,
+    first_name: Option<String>,
+    last_name: Option<String>,
+) -> AdminResult {
+    let email = match email {
+        Some(email) => email,
+        None => prompt("Email")?,
+    };
+    let email = normalize_email(&email, &settings.email_normalization)?;
+    let first_name = match first_name {
+        Some(first_name) => first_name,
+        None => prompt("First name")?,
+    };
+    let last_name = match last_name {
+        Some(last_name) => last_name,
+        None => prompt("Last name")?,
+    };
+    let password = prompt_new_password(settings, &[&email.address, &first_name, &last_name])?;
+
+    let mut transaction = pool.begin().await?;
+    let user_id: uuid::Uuid = sqlx::query(
+        "INSERT INTO users \
+        (email, email_normalized, password, first_name, last_name, is_active, is_staff, is_superuser) \
+        VALUES ($1, $2, $3, $4, $5, TRUE, TRUE, TRUE) RETURNING id",
+    )
+    .bind(&email.address)
+    .bind(&email.canonical)
+    .bind(hash(password.as_bytes(), &settings.password_hashing).await)
+    .bind(&first_name)
+    .bind(&last_name)
+    .map(|row: PgRow| row.get("id"))
+    .fetch_one(&mut *transaction)
+    .await?;
+    sqlx::query("INSERT INTO user_profile (user_id) VALUES ($1)")
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await?;
+    transaction.commit().await?;
+
+    println!("Superuser {} created with id {}", email.address, user_id);
+    Ok(())
+}
+
+async fn set_active(
+    pool: &PgPool,
+    settings: &Settings,
+    email: &str,
+    is_active: bool,
+) -> AdminResult {
+    let email = normalize_email(email, &settings.email_normalization)?;
+    let updated = sqlx::query("UPDATE users SET is_active = $1 WHERE email_normalized = $2")
+        .bind(is_active)
+        .bind(&email.canonical)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+    if updated == 0 {
+        return Err(format!("User {} not found", email.address).into());
+    }
+    println!(
+        "User {} {}",
+        email.address,
+        if is_active {
+            "activated"
+        } else {
+            "deactivated"
+        }
+    );
+    Ok(())
+}
+
+async fn reset_password(pool: &PgPool, settings: &Settings, email: &str) -> AdminResult {
+    let email = normalize_email(email, &settings.email_normalization)?;
+    let user =
+        sqlx::query("SELECT id, first_name, last_name FROM users WHERE email_normalized = $1")
+            .bind(&email.canonical)
+            .map(|row: PgRow| -> (uuid::Uuid, String, String) {
+                (row.get("id"), row.get("first_name"), row.get("last_name"))
+            })
+            .fetch_optional(pool)
+            .await?;
+    let (user_id, first_name, last_name) =
+        user.ok_or_else(|| format!("User {} not found", email.address))?;
+
+    let password = prompt_new_password(settings, &[&email.address, &first_name, &last_name])?;
+    sqlx::query("UPDATE users SET password = $1 WHERE id = $2")
+        .bind(hash(password.as_bytes(), &settings.password_hashing).await)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    println!("Password for {} has been reset", email.address);
+    Ok(())
+}
+
+async fn normalize_emails(pool: &PgPool, settings: &Settings, dry_run: bool) -> AdminResult {
+    let report = renormalize_emails(pool, &settings.email_normalization, dry_run).await?;
+    for conflict in &report.conflicts {
+        eprintln!("Skipped {}", conflict);
+    }
+    let action = if dry_run {
+        "Would re-normalize"
+    } else {
+        "Re-normalized"
+    };
+    println!("{} {} emails", action, report.updated);
+    Ok(())
+}
+
+async fn list_users(pool: &PgPool, is_active: Option<bool>, limit: i64) -> AdminResult {
+    let users = sqlx::query(
+        "SELECT id, email, first_name, last_name, is_active, is_staff, is_superuser, date_joined \
+        FROM users WHERE ($1::BOOLEAN IS NULL OR is_active = $1) \
+        ORDER BY date_joined DESC LIMIT $2",
+    )
+    .bind(is_active)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    println!(
+        "{:<36}  {:<32}  {:<24}  {:<6}  {:<5}  {:<5}  date_joined",
+        "id", "email", "name", "active", "staff", "super"
+    );
+    for user in &users {
+        let date_joined: DateTime<Utc> = user.get("date_joined");
+        let name = format!(
+            "{} {}",
+            user.get::<String, _>("first_name"),
+            user.get::<String, _>("last_name")
+        );
+        println!(
+            "{:<36}  {:<32}  {:<24}  {:<6}  {:<5}  {:<5}  {}",
+            user.get::<uuid::Uuid, _>("id"),
+            user.get::<String, _>("email"),
+            name,
+            user.get::<Option<bool>, _>("is_active").unwrap_or(false),
+            user.get::<Option<bool>, _>("is_staff").unwrap_or(false),
+            user.get::<Option<bool>, _>("is_superuser").unwrap_or(false),
+            date_joined.to_rfc3339(),
+        );
+    }
+    println!("{} user(s)", users.len());
+    Ok(())
+}
+
+async fn purge_unactivated(
+    pool: &PgPool,
+    settings: &Settings,
+    older_than_hours: Option<i64>,
+    dry_run: bool,
+) -> AdminResult {
+    let older_than_minutes = older_than_hours
+        .map(|hours| hours * 60)
+        .unwrap_or(settings.secret.token_expiration);
+
+    let query = if dry_run {
+        "SELECT COUNT(*) AS affected FROM users \
+        WHERE is_active = FALSE AND date_joined < NOW() - make_interval(mins => $1)"
+    } else {
+        "WITH deleted AS (DELETE FROM users \
+        WHERE is_active = FALSE AND date_joined < NOW() - make_interval(mins => $1) \
+        RETURNING id) SELECT COUNT(*) AS affected FROM deleted"
+    };
+    let affected: i64 = sqlx::query(query)
+        .bind(older_than_minutes as i32)
+        .map(|row: PgRow| row.get("affected"))
+        .fetch_one(pool)
+        .await?;
+
+    if dry_run {
+        println!("{} unactivated account(s) would be deleted", affected);
+    } else {
+        println!("{} unactivated account(s) deleted", affected);
+    }
+    Ok(())
+}
+
+async fn resend_verification(pool: &PgPool, settings: &Settings, email: &str) -> AdminResult {
+    let email = normalize_email(email, &settings.email_normalization)?;
+    let user = sqlx::query(
+        "SELECT id, email, first_name, last_name, is_active, locale FROM users \
+        WHERE email_normalized = $1",
+    )
+    .bind(&email.canonical)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| format!("User {} not found", email.address))?;
+
+    if user.get::<Option<bool>, _>("is_active").unwrap_or(false) {
+        return Err(format!("User {} is already active", email.address).into());
+    }
+
+    let redis_pool = get_redis_pool(&settings.redis);
+    let mut redis_con = redis_pool.get().await?;
+    let recipient = EmailRecipient {
+        user_id: user.get("id"),
+        email: user.get("email"),
+        first_name: user.get("first_name"),
+        last_name: user.get("last_name"),
+        locale: user
+            .get::<Option<String>, _>("locale")
+            .as_deref()
+            .and_then(Locale::from_tag)
+            .unwrap_or_default(),
+    };
+    let tasks = BackgroundTasks::default();
+    let sending = send_multipart_email(
+        recipient,
+        "verification_email",
+        None,
+        &mut redis_con,
+        &tasks,
+    )
+    .await?;
+    // Письмо отправляется в фоновой задаче; дожидаемся её, пока процесс не завершился.
+    sending.await??;
+
+    println!("Verification email sent to {}", email.address);
+    Ok(())
+}
+
+fn generate_secrets() {
+    // `secret_key` — ключ `SymmetricKey<V4>`: ровно 32 байта. Алфавит из 64 символов
+    // делает каждый символ равновероятным.
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    let mut secret_key = [0_u8; 32];
+    OsRng.fill_bytes(&mut secret_key);
+    let secret_key: String = secret_key
+        .iter()
+        .map(|byte| ALPHABET[(*byte % 64) as usize] as char)
+        .collect();
+
+    // `hmac_secret` — ключ cookie сессии, `Key::from` требует не меньше 64 байт.
+    let mut hmac_secret = [0_u8; 64];
+    OsRng.fill_bytes(&mut hmac_secret);
+
+    println!("secret:");
+    println!("  secret_key: \"{}\"", secret_key);
+    println!("  hmac_secret: \"{}\"", hex::encode(hmac_secret));
+}
+
+fn prompt(label: &str) -> io::Result<String> {
+    print!("{}: ", label);
+    io::stdout().flush()?;
+    let mut value = String::new();
+    io::stdin().lock().read_line(&mut value)?;
+    Ok(value.trim().to_string())
+}
+
+/// Запрашивает пароль дважды и проверяет его по политике паролей из настроек.
+fn prompt_new_password(
+    settings: &Settings,
+    user_inputs: &[&str],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let password = rpassword::prompt_password("Password: ")?;
+    if rpassword::prompt_password("Password (again): ")? != password {
+        return Err("Passwords do not match".into());
+    }
+
+    if let Err(errors) = validate_password(
+        &settings.password_policy,
+        &password,
+        user_inputs,
+        Locale::En,
+    ) {
+        let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+        return Err(format!("Password rejected: {}", messages.join(" ")).into());
+    }
+    Ok(password)
+}
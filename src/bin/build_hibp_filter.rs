@@ -0,0 +1,90 @@
+//! Строит файл фильтра Блума для `breached_passwords.format: bloom`
+//! из исходных данных Pwned Passwords (SHA-1):
+//! одного файла строк `SHA1:COUNT` или каталога range-файлов `XXXXX.txt` со строками `SUFFIX:COUNT`.
+//!
+//! \`\`\`
+//! cargo run --release --bin build-hibp-filter -- pwnedpasswords.txt data/pwned-passwords.bloom
+//! \`\`\`
+use backend::utils::{parse_hash_line, BloomFilter};
+use clap::Parser;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Result, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(about = "Build a breached password bloom filter from the Pwned Passwords SHA-1 dataset")]
+struct Args {
+    /// Файл `SHA1:COUNT` или каталог range-файлов `XXXXX.txt`.
+    input: PathBuf,
+    /// Куда записать фильтр.
+    output: PathBuf,
+    /// Допустимая вероятность ложного срабатывания.
+    #[arg(long, default_value_t = 0.001)]
+    false_positive_rate: f64,
+    /// Пропускать пароли, встречавшиеся в утечках реже указанного числа раз.
+    #[arg(long, default_value_t = 1)]
+    min_count: u64,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let sources = source_files(&args.input)?;
+
+    // Первый проход: считаем подходящие хэши, чтобы подобрать размер фильтра.
+    let mut expected_items = 0_u64;
+    for_each_hash(&sources, args.min_count, |_| expected_items += 1)?;
+    println!(
+        "{} hashes with at least {} occurrence(s)",
+        expected_items, args.min_count
+    );
+
+    let mut filter = BloomFilter::new(expected_items, args.false_positive_rate);
+    for_each_hash(&sources, args.min_count, |digest| filter.insert(digest))?;
+
+    let mut writer = BufWriter::new(File::create(&args.output)?);
+    filter.write_to(&mut writer)?;
+    writer.flush()?;
+
+    println!("Bloom filter written to {}", args.output.display());
+    Ok(())
+}
+
+/// Список исходных файлов с префиксом хэша, который нужно дописать к каждой строке.
+fn source_files(input: &Path) -> Result<Vec<(PathBuf, String)>> {
+    if !input.is_dir() {
+        return Ok(vec![(input.to_path_buf(), String::new())]);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(input)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "txt") {
+            continue;
+        }
+        if let Some(prefix) = path.file_stem().and_then(|stem| stem.to_str()) {
+            let prefix = prefix.to_string();
+            files.push((path, prefix));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn for_each_hash(
+    sources: &[(PathBuf, String)],
+    min_count: u64,
+    mut callback: impl FnMut(&[u8; 20]),
+) -> Result<()> {
+    for (path, prefix) in sources {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            match parse_hash_line(prefix, &line) {
+                Some((digest, count)) if count >= min_count => callback(&digest),
+                Some(_) => {}
+                None if line.trim().is_empty() => {}
+                None => eprintln!("Skipping malformed line in {}: {}", path.display(), line),
+            }
+        }
+    }
+    Ok(())
+}
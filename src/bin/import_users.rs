@@ -0,0 +1,156 @@
+//! Массовый импорт пользователей из старой системы вместе с их хэшами паролей.
+//! Хэши сохраняются как есть и заменяются на Argon2 при первом успешном входе.
+//!
+//! Поддерживаются CSV с заголовком и JSON-массив объектов с полями:
+//! `email`, `password_hash`, `first_name`, `last_name`,
+//! необязательные `is_active`, `is_staff`, `is_superuser`, `date_joined` (RFC 3339).
+//!
+//! \`\`\`
+//! cargo run --bin import-users -- users.csv
+//! cargo run --bin import-users -- --dry-run users.json
+//! \`\`\`
+use backend::settings::get_settings;
+use backend::startup::get_connection_pool;
+use backend::utils::{normalize_email, HashScheme};
+use chrono::{DateTime, Utc};
+use clap::{Parser, ValueEnum};
+use dotenv::dotenv;
+use serde::Deserialize;
+use sqlx::Row;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(about = "Import users with their existing password hashes")]
+struct Args {
+    /// CSV- или JSON-файл с пользователями.
+    input: PathBuf,
+    /// Формат файла; по умолчанию определяется по расширению.
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// Только проверить файл, ничего не записывая в БД.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
+}
+
+#[derive(Deserialize)]
+struct ImportedUser {
+    email: String,
+    password_hash: String,
+    first_name: String,
+    last_name: String,
+    #[serde(default = "default_true")]
+    is_active: bool,
+    #[serde(default)]
+    is_staff: bool,
+    #[serde(default)]
+    is_superuser: bool,
+    date_joined: Option<DateTime<Utc>>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenv().ok();
+    let args = Args::parse();
+
+    let format = match args.format {
+        Some(format) => format,
+        None if args.input.extension().is_some_and(|ext| ext == "json") => Format::Json,
+        None => Format::Csv,
+    };
+    let users = read_users(&args.input, format)?;
+
+    // Пользователей с нераспознанным форматом хэша не импортируем: войти они всё равно не смогут.
+    let (valid, invalid): (Vec<_>, Vec<_>) = users
+        .into_iter()
+        .partition(|user| HashScheme::detect(&user.password_hash).is_some());
+    for user in &invalid {
+        eprintln!("Skipping {}: unsupported password hash format", user.email);
+    }
+    println!(
+        "{} user(s) to import, {} skipped",
+        valid.len(),
+        invalid.len()
+    );
+
+    if args.dry_run {
+        return Ok(());
+    }
+
+    let settings = get_settings()?;
+    let pool = get_connection_pool(&settings.database).await;
+    let mut transaction = pool.begin().await?;
+
+    let mut imported = 0;
+    for user in &valid {
+        let email = match normalize_email(&user.email, &settings.email_normalization) {
+            Ok(email) => email,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", user.email, e);
+                continue;
+            }
+        };
+
+        let user_id = sqlx::query(
+            "INSERT INTO users \
+            (email, email_normalized, password, first_name, last_name, is_active, is_staff, \
+            is_superuser, date_joined) \
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, COALESCE($9, NOW())) \
+            ON CONFLICT (email_normalized) DO NOTHING \
+            RETURNING id",
+        )
+        .bind(&email.address)
+        .bind(&email.canonical)
+        .bind(&user.password_hash)
+        .bind(&user.first_name)
+        .bind(&user.last_name)
+        .bind(user.is_active)
+        .bind(user.is_staff)
+        .bind(user.is_superuser)
+        .bind(user.date_joined)
+        .map(|row: sqlx::postgres::PgRow| -> uuid::Uuid { row.get("id") })
+        .fetch_optional(&mut *transaction)
+        .await?;
+
+        match user_id {
+            Some(user_id) => {
+                sqlx::query(
+                    "INSERT INTO user_profile (user_id) VALUES ($1) ON CONFLICT (user_id) DO NOTHING",
+                )
+                .bind(user_id)
+                .execute(&mut *transaction)
+                .await?;
+                imported += 1;
+            }
+            None => eprintln!("Skipping {}: user already exists", user.email),
+        }
+    }
+
+    transaction.commit().await?;
+    println!("{} user(s) imported", imported);
+    Ok(())
+}
+
+fn read_users(
+    path: &PathBuf,
+    format: Format,
+) -> Result<Vec<ImportedUser>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        Format::Json => Ok(serde_json::from_reader(reader)?),
+        Format::Csv => Ok(csv::Reader::from_reader(reader)
+            .into_deserialize()
+            .collect::<Result<Vec<ImportedUser>, _>>()?),
+    }
+}
@@ -0,0 +1,113 @@
+//! Локализация ответов API и писем. Сообщения хранятся в каталогах `locales/<язык>.json`
+//! по стабильным кодам (`error.not_logged_in`); если перевода нет, используется английский.
+use crate::types::USER_LOCALE_KEY;
+use actix_session::SessionExt;
+use actix_web::http::header::ACCEPT_LANGUAGE;
+use actix_web::HttpRequest;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Поддерживаемые языки. Код языка хранится в `users.locale` и в сессии.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ru,
+}
+
+impl Locale {
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ru => "ru",
+        }
+    }
+
+    /// Язык по тегу BCP 47: `ru`, `ru-RU` и `ru_RU` дают `Ru`.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_lowercase();
+        match primary.as_str() {
+            "en" => Some(Locale::En),
+            "ru" => Some(Locale::Ru),
+            _ => None,
+        }
+    }
+
+    /// Поддерживаемый язык с наибольшим весом `q` из заголовка `Accept-Language`;
+    /// при равных весах — первый.
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';');
+                let locale = Self::from_tag(parts.next()?)?;
+                let weight = match parts.find_map(|part| part.trim().strip_prefix("q=")) {
+                    Some(weight) => weight.parse::<f32>().ok()?,
+                    None => 1.0,
+                };
+                Some((locale, weight))
+            })
+            .filter(|(_, weight)| *weight > 0.0)
+            .fold(None, |best: Option<(Self, f32)>, candidate| match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            })
+            .map(|(locale, _)| locale)
+    }
+}
+
+static CATALOGS: Lazy<HashMap<Locale, HashMap<String, String>>> = Lazy::new(|| {
+    [
+        (Locale::En, include_str!("../locales/en.json")),
+        (Locale::Ru, include_str!("../locales/ru.json")),
+    ]
+    .into_iter()
+    .map(|(locale, source)| {
+        let catalog = serde_json::from_str(source)
+            .unwrap_or_else(|e| panic!("Invalid message catalog for {}: {}", locale.code(), e));
+        (locale, catalog)
+    })
+    .collect()
+});
+
+/// Сообщение по коду на нужном языке.
+pub fn t(locale: Locale, code: &str) -> String {
+    t_with(locale, code, &[])
+}
+
+/// Сообщение по коду с подстановкой параметров `{name}`.
+/// Нет перевода — английский вариант, нет и его — сам код.
+pub fn t_with(locale: Locale, code: &str, args: &[(&str, &dyn Display)]) -> String {
+    let message = CATALOGS
+        .get(&locale)
+        .and_then(|catalog| catalog.get(code))
+        .or_else(|| CATALOGS[&Locale::En].get(code));
+    let message = match message {
+        Some(message) => message.as_str(),
+        None => {
+            tracing::event!(target: "backend", tracing::Level::WARN, "Missing message {} in the catalogs", code);
+            code
+        }
+    };
+
+    args.iter()
+        .fold(message.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{}}}", name), &value.to_string())
+        })
+}
+
+/// Язык ответа: выбор, сохранённый в сессии при входе, затем `Accept-Language`, иначе английский.
+pub fn request_locale(req: &HttpRequest) -> Locale {
+    if let Ok(Some(locale)) = req.get_session().get::<Locale>(USER_LOCALE_KEY) {
+        return locale;
+    }
+
+    req.headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or_default()
+}
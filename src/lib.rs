@@ -1,13 +1,10 @@
+pub mod i18n;
+pub mod metrics;
+pub mod middleware;
 pub mod routes;
 pub mod settings;
 pub mod startup;
 pub mod telemetry;
+pub mod tls;
 pub mod types;
 pub mod utils;
-
-pub static ENV: once_cell::sync::Lazy<minijinja::Environment<'static>> =
-    once_cell::sync::Lazy::new(|| {
-        let mut env = minijinja::Environment::new();
-        env.set_source(minijinja::Source::from_path("templates"));
-        env
-    });
@@ -1,6 +1,6 @@
 use backend::settings::get_settings;
 use backend::startup::Application;
-use backend::telemetry::{get_subscriber, init_subscriber};
+use backend::telemetry::{get_subscriber, init_subscriber, otlp_tracer, shutdown_otlp_tracer};
 use dotenv::dotenv;
 use std::io::Result;
 
@@ -11,8 +11,14 @@ async fn main() -> Result<()> {
     let settings =
         get_settings().expect("Failed to read settings (Не удалось прочитать настройки).");
 
-    let subscriber = get_subscriber(settings.clone().debug);
+    let tracer = otlp_tracer(&settings.otlp)
+        .expect("Failed to start OTLP exporter (Не удалось запустить экспорт трейсов OTLP).");
+    let subscriber = get_subscriber(settings.clone().debug, tracer);
     init_subscriber(subscriber);
+    if settings.otlp.enabled {
+        tracing::event!(target: "backend", tracing::Level::INFO, "Exporting traces to {}",
+            settings.otlp.endpoint);
+    }
 
     let application = Application::build(settings, None).await?;
 
@@ -20,5 +26,6 @@ async fn main() -> Result<()> {
         application.port());
 
     application.run_until_stopped().await?;
+    shutdown_otlp_tracer().await;
     Ok(())
 }
@@ -0,0 +1,196 @@
+//! Метрики приложения в формате Prometheus.
+//! Все метрики регистрируются в собственном реестре при первом обращении к `METRICS`
+//! и отдаются эндпоинтом `/metrics`.
+use crate::types::{AuthEventKind, NewAuthEvent};
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use std::time::Duration;
+
+pub struct Metrics {
+    registry: Registry,
+    http_request_duration: HistogramVec,
+    login_attempts: IntCounterVec,
+    registrations: IntCounterVec,
+    activations: IntCounterVec,
+    token_verification_failures: IntCounterVec,
+    emails: IntCounterVec,
+    db_pool_connections: IntGaugeVec,
+    redis_pool_connections: IntGaugeVec,
+}
+
+pub static METRICS: Lazy<Metrics> = Lazy::new(Metrics::new);
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by method, route pattern and status code.",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("Invalid metric definition.");
+        let login_attempts = IntCounterVec::new(
+            Opts::new(
+                "auth_login_attempts_total",
+                "Login attempts by outcome and failure reason.",
+            ),
+            &["outcome", "reason"],
+        )
+        .expect("Invalid metric definition.");
+        let registrations = IntCounterVec::new(
+            Opts::new(
+                "auth_registrations_total",
+                "Registration attempts by outcome.",
+            ),
+            &["outcome"],
+        )
+        .expect("Invalid metric definition.");
+        let activations = IntCounterVec::new(
+            Opts::new(
+                "auth_activations_total",
+                "Account activation attempts by outcome.",
+            ),
+            &["outcome"],
+        )
+        .expect("Invalid metric definition.");
+        let token_verification_failures = IntCounterVec::new(
+            Opts::new(
+                "auth_token_verification_failures_total",
+                "Confirmation token verification failures by cause.",
+            ),
+            &["cause"],
+        )
+        .expect("Invalid metric definition.");
+        let emails = IntCounterVec::new(
+            Opts::new("emails_total", "Outgoing emails by outcome."),
+            &["outcome"],
+        )
+        .expect("Invalid metric definition.");
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Postgres pool connections by state."),
+            &["state"],
+        )
+        .expect("Invalid metric definition.");
+        let redis_pool_connections = IntGaugeVec::new(
+            Opts::new("redis_pool_connections", "Redis pool connections by state."),
+            &["state"],
+        )
+        .expect("Invalid metric definition.");
+
+        for collector in [
+            Box::new(http_request_duration.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(login_attempts.clone()),
+            Box::new(registrations.clone()),
+            Box::new(activations.clone()),
+            Box::new(token_verification_failures.clone()),
+            Box::new(emails.clone()),
+            Box::new(db_pool_connections.clone()),
+            Box::new(redis_pool_connections.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("Failed to register metric.");
+        }
+
+        Self {
+            registry,
+            http_request_duration,
+            login_attempts,
+            registrations,
+            activations,
+            token_verification_failures,
+            emails,
+            db_pool_connections,
+            redis_pool_connections,
+        }
+    }
+
+    /// `route` — шаблон маршрута (`/users/login/`), а не фактический путь,
+    /// чтобы число рядов метрики не зависело от параметров запроса.
+    pub fn observe_http_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_request_duration
+            .with_label_values(&[method, route, &status.to_string()])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Счётчики входа, регистрации и активации ведутся по тем же событиям,
+    /// что пишутся в журнал `auth_events`.
+    pub fn observe_auth_event(&self, event: &NewAuthEvent) {
+        let outcome = event.outcome.as_str();
+        match event.kind {
+            AuthEventKind::Login => {
+                let reason = event
+                    .details
+                    .as_ref()
+                    .and_then(|details| details.get("reason"))
+                    .and_then(|reason| reason.as_str())
+                    .unwrap_or("");
+                self.login_attempts
+                    .with_label_values(&[outcome, reason])
+                    .inc();
+            }
+            AuthEventKind::Registration => {
+                self.registrations.with_label_values(&[outcome]).inc();
+            }
+            AuthEventKind::Activation => {
+                self.activations.with_label_values(&[outcome]).inc();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn token_verification_failed(&self, cause: &str) {
+        self.token_verification_failures
+            .with_label_values(&[cause])
+            .inc();
+    }
+
+    pub fn email_sent(&self) {
+        self.emails.with_label_values(&["sent"]).inc();
+    }
+
+    pub fn email_failed(&self) {
+        self.emails.with_label_values(&["failed"]).inc();
+    }
+
+    /// Снимает состояние пулов и отдаёт все метрики в текстовом формате Prometheus.
+    /// Число замеров запросов с такими метками.
+    #[cfg(test)]
+    pub(crate) fn http_requests_observed(&self, method: &str, route: &str, status: u16) -> u64 {
+        self.http_request_duration
+            .with_label_values(&[method, route, &status.to_string()])
+            .get_sample_count()
+    }
+
+    pub fn render(&self, db_pool: &PgPool, redis_pool: &deadpool_redis::Pool) -> String {
+        self.db_pool_connections
+            .with_label_values(&["total"])
+            .set(db_pool.size() as i64);
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(db_pool.num_idle() as i64);
+
+        let status = redis_pool.status();
+        self.redis_pool_connections
+            .with_label_values(&["max"])
+            .set(status.max_size as i64);
+        self.redis_pool_connections
+            .with_label_values(&["total"])
+            .set(status.size as i64);
+        self.redis_pool_connections
+            .with_label_values(&["available"])
+            .set(status.available as i64);
+
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("Failed to encode metrics.");
+        String::from_utf8(buffer).expect("Metrics are not valid UTF-8.")
+    }
+}
@@ -0,0 +1,95 @@
+use crate::i18n::{request_locale, t};
+use crate::types::{ErrorResponse, USER_ID_KEY};
+use crate::utils::{verify_csrf_token, CSRF_HEADER};
+use actix_session::SessionExt;
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::http::Method;
+use actix_web::{Error, HttpResponse};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use uuid::Uuid;
+
+/// Защита от CSRF по схеме synchronizer token: запросы POST, PUT, PATCH и DELETE
+/// от вошедшего пользователя должны содержать заголовок `X-CSRF-Token`
+/// с токеном его сессии (`GET /users/csrf-token/`), иначе ответ 403.
+///
+/// Запросы с `Authorization: Bearer` не проверяются: браузер не добавляет этот заголовок сам,
+/// а чужой сайт не может выставить его без разрешения CORS.
+///
+/// Middleware читает сессию, поэтому должен быть внутри `SessionMiddleware`
+/// (его `wrap` — раньше).
+pub struct CsrfProtection;
+
+impl<S, B> Transform<S, ServiceRequest> for CsrfProtection
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfProtectionMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(CsrfProtectionMiddleware { service }))
+    }
+}
+
+pub struct CsrfProtectionMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfProtectionMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if requires_csrf_token(&req) && !has_valid_csrf_token(&req) {
+            tracing::event!(target: "backend", tracing::Level::WARN, "Rejected {} {} without a valid CSRF token", req.method(), req.path());
+            let response = HttpResponse::Forbidden().json(ErrorResponse {
+                error: t(request_locale(req.request()), "error.csrf_invalid"),
+            });
+            return Box::pin(ready(Ok(req.into_response(response).map_into_right_body())));
+        }
+
+        let future = self.service.call(req);
+        Box::pin(async move { future.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// Токен нужен для изменяющих запросов в сессии вошедшего пользователя
+/// (или с повреждённой сессией), кроме клиентов с bearer-токеном.
+fn requires_csrf_token(req: &ServiceRequest) -> bool {
+    let changes_state = matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let is_bearer_client = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("Bearer "));
+
+    changes_state
+        && !is_bearer_client
+        && !matches!(req.get_session().get::<Uuid>(USER_ID_KEY), Ok(None))
+}
+
+fn has_valid_csrf_token(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(CSRF_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|token| verify_csrf_token(&req.get_session(), token))
+}
@@ -0,0 +1,109 @@
+use crate::metrics::METRICS;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::time::Instant;
+
+/// Замеряет длительность каждого запроса и пишет её в гистограмму
+/// `http_request_duration_seconds` с шаблоном маршрута и кодом ответа.
+pub struct RequestMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestMetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestMetricsMiddleware { service }))
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestMetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let future = self.service.call(req);
+
+        Box::pin(async move {
+            match future.await {
+                Ok(response) => {
+                    // Шаблон маршрута известен только после маршрутизации.
+                    let route = response
+                        .request()
+                        .match_pattern()
+                        .unwrap_or_else(|| "unmatched".to_string());
+                    METRICS.observe_http_request(
+                        &method,
+                        &route,
+                        response.status().as_u16(),
+                        started.elapsed(),
+                    );
+                    Ok(response)
+                }
+                Err(e) => {
+                    // Ошибку вернул внутренний middleware: запроса вместе с маршрутом уже нет,
+                    // код ответа берём из самой ошибки.
+                    METRICS.observe_http_request(
+                        &method,
+                        "unmatched",
+                        e.as_response_error().status_code().as_u16(),
+                        started.elapsed(),
+                    );
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn errors_from_inner_middleware_are_observed() {
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|_, _| async {
+                    Err::<ServiceResponse, _>(actix_web::error::ErrorImATeapot("rejected"))
+                })
+                .wrap(RequestMetrics)
+                .route("/", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let before = METRICS.http_requests_observed("GET", "unmatched", 418);
+        let result = app
+            .call(test::TestRequest::get().uri("/").to_request())
+            .await;
+        assert!(result.is_err());
+        assert_eq!(
+            METRICS.http_requests_observed("GET", "unmatched", 418),
+            before + 1
+        );
+    }
+}
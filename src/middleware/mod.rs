@@ -0,0 +1,9 @@
+mod csrf;
+mod metrics;
+mod request_id;
+mod security_headers;
+
+pub use csrf::CsrfProtection;
+pub use metrics::RequestMetrics;
+pub use request_id::{RequestId, RequestTracing, REQUEST_ID_HEADER};
+pub use security_headers::SecurityHeaders;
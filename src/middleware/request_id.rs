@@ -0,0 +1,192 @@
+use crate::utils::client_ip;
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::{ErrorInternalServerError, InternalError};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE};
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse};
+use opentelemetry::propagation::Extractor;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Заголовок с идентификатором запроса во входящих запросах и в ответах.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Самый длинный идентификатор, который принимается от клиента или прокси.
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// Идентификатор текущего запроса, доступен обработчикам через `HttpRequest::extensions`.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Принимает `X-Request-Id` от клиента или прокси, а если его нет или он некорректен,
+/// выдаёт новый UUID. Весь запрос выполняется в корневом span `http_request`
+/// с идентификатором, IP клиента, методом, шаблоном маршрута и кодом ответа:
+/// в этот span попадают и фоновые задачи из `BackgroundTasks::spawn`.
+/// Идентификатор возвращается в заголовке ответа и в поле `request_id` JSON-ответов с ошибкой.
+/// При экспорте в OTLP span продолжает трейс из заголовка `traceparent` (W3C Trace Context).
+pub struct RequestTracing;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestTracing
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Transform = RequestTracingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestTracingMiddleware { service }))
+    }
+}
+
+pub struct RequestTracingMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestTracingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| is_valid_request_id(value))
+            .map_or_else(|| uuid::Uuid::new_v4().to_string(), str::to_string);
+        req.extensions_mut().insert(RequestId(request_id.clone()));
+
+        let span = tracing::info_span!(
+            target: "backend",
+            "http_request",
+            request_id = %request_id,
+            client_ip = %client_ip(req.request()).unwrap_or_default(),
+            method = %req.method(),
+            route = tracing::field::Empty,
+            status = tracing::field::Empty,
+            otel.name = tracing::field::Empty,
+            otel.kind = "server",
+        );
+        let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&HeaderExtractor(req.headers()))
+        });
+        span.set_parent(parent);
+        // Клон `HttpRequest` здесь держать нельзя: маршрутизатору нужен единственный владелец запроса.
+        let future = span.in_scope(|| self.service.call(req));
+
+        Box::pin(
+            async move {
+                let response = match future.await {
+                    Ok(response) => response.map_into_boxed_body(),
+                    // Ошибку внутреннего middleware отдаём тем же ответом, что и actix,
+                    // но с идентификатором запроса.
+                    Err(e) => {
+                        let response = e.error_response();
+                        record_response(None, response.status());
+                        let response = with_request_id(response, &request_id).await?;
+                        return Err(InternalError::from_response(e, response).into());
+                    }
+                };
+
+                // Шаблон маршрута известен только после маршрутизации.
+                record_response(Some(response.request()), response.status());
+                let (request, response) = response.into_parts();
+                let response = with_request_id(response, &request_id).await?;
+                Ok(ServiceResponse::new(request, response))
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Чтение заголовков запроса для `TextMapPropagator`.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// Видимые ASCII-символы без пробелов: значение попадает в заголовки и логи как есть.
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Записывает маршрут и код ответа в span запроса.
+fn record_response(request: Option<&HttpRequest>, status: StatusCode) {
+    let span = tracing::Span::current();
+    let route = request
+        .and_then(HttpRequest::match_pattern)
+        .unwrap_or_else(|| "unmatched".to_string());
+    span.record("route", route.as_str());
+    if let Some(request) = request {
+        span.record(
+            "otel.name",
+            format!("{} {}", request.method(), route).as_str(),
+        );
+    }
+    span.record("status", status.as_u16());
+    tracing::event!(target: "backend", tracing::Level::INFO, "Request finished with status {}", status.as_u16());
+}
+
+/// Добавляет заголовок `X-Request-Id`, а в JSON-ответ с ошибкой (`ErrorResponse`
+/// и производные) — поле `request_id`. Остальные тела не трогаются.
+async fn with_request_id(
+    response: HttpResponse<BoxBody>,
+    request_id: &str,
+) -> Result<HttpResponse<BoxBody>, Error> {
+    let is_json_error = (response.status().is_client_error()
+        || response.status().is_server_error())
+        && response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+
+    let mut response = if is_json_error {
+        let (head, body) = response.into_parts();
+        let bytes = to_bytes(body).await.map_err(ErrorInternalServerError)?;
+        let bytes = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+            Ok(serde_json::Value::Object(mut body)) if body.contains_key("error") => {
+                body.insert("request_id".to_string(), request_id.into());
+                serde_json::to_vec(&body).map_or(bytes, Bytes::from)
+            }
+            _ => bytes,
+        };
+        let mut response = head.set_body(BoxBody::new(bytes));
+        response.headers_mut().remove(CONTENT_LENGTH);
+        response
+    } else {
+        response
+    };
+
+    if let Ok(value) = HeaderValue::from_str(request_id) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("x-request-id"), value);
+    }
+    Ok(response)
+}
@@ -0,0 +1,121 @@
+use crate::settings::SecurityHeadersSettings;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_SECURITY_POLICY, REFERRER_POLICY,
+    STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use actix_web::Error;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Добавляет к ответам заголовки безопасности из `security_headers`.
+/// Заголовки, которые уже выставил обработчик, не перезаписываются.
+#[derive(Clone)]
+pub struct SecurityHeaders {
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl SecurityHeaders {
+    /// Значения заголовков проверяются один раз при создании; некорректное значение — ошибка запуска.
+    pub fn new(settings: &SecurityHeadersSettings) -> Result<Self, String> {
+        let mut headers = Vec::new();
+        if settings.enabled {
+            headers.push((X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()));
+            if settings.hsts_max_age_seconds > 0 {
+                let mut hsts = format!("max-age={}", settings.hsts_max_age_seconds);
+                if settings.hsts_include_subdomains {
+                    hsts.push_str("; includeSubDomains");
+                }
+                if settings.hsts_preload {
+                    hsts.push_str("; preload");
+                }
+                headers.push((STRICT_TRANSPORT_SECURITY, hsts));
+            }
+            let optional = [
+                (CONTENT_SECURITY_POLICY, &settings.content_security_policy),
+                (X_FRAME_OPTIONS, &settings.frame_options),
+                (REFERRER_POLICY, &settings.referrer_policy),
+                (
+                    HeaderName::from_static("permissions-policy"),
+                    &settings.permissions_policy,
+                ),
+            ];
+            for (name, value) in optional {
+                if let Some(value) = value {
+                    headers.push((name, value.clone()));
+                }
+            }
+        }
+
+        let headers = headers
+            .into_iter()
+            .map(|(name, value)| {
+                HeaderValue::from_str(&value)
+                    .map(|value| (name.clone(), value))
+                    .map_err(|e| format!("Invalid value for the {} header: {}", name, e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            headers: Arc::new(headers),
+        })
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for SecurityHeaders
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = SecurityHeadersMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(SecurityHeadersMiddleware {
+            service,
+            headers: self.headers.clone(),
+        }))
+    }
+}
+
+pub struct SecurityHeadersMiddleware<S> {
+    service: S,
+    headers: Arc<Vec<(HeaderName, HeaderValue)>>,
+}
+
+impl<S, B> Service<ServiceRequest> for SecurityHeadersMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let headers = self.headers.clone();
+        let future = self.service.call(req);
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            insert_missing(response.headers_mut(), &headers);
+            Ok(response)
+        })
+    }
+}
+
+fn insert_missing(response_headers: &mut HeaderMap, headers: &[(HeaderName, HeaderValue)]) {
+    for (name, value) in headers {
+        if !response_headers.contains_key(name) {
+            response_headers.insert(name.clone(), value.clone());
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::i18n::{request_locale, t, Locale};
+use crate::routes::dev::email_html_response;
+use crate::settings::Settings;
+use crate::types::ErrorResponse;
+use crate::utils::{normalize_text, preview_context, render_email, Normalize, ValidatedQuery};
+use actix_web::http::header::ContentType;
+use actix_web::web::{Data, Path};
+use actix_web::{get, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use tracing::instrument;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+pub struct PreviewParameters {
+    /// Язык письма; по умолчанию — язык запроса.
+    #[validate(length(min = 2, max = 35))]
+    locale: Option<String>,
+    #[serde(default)]
+    format: PreviewFormat,
+}
+
+impl Normalize for PreviewParameters {
+    fn normalize(&mut self) {
+        if let Some(locale) = self.locale.as_mut() {
+            normalize_text(locale);
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum PreviewFormat {
+    #[default]
+    Html,
+    Text,
+}
+
+/// Предпросмотр письма с тестовыми данными: `/dev/emails/verification_email?locale=ru&format=text`.
+/// Шаблоны читаются так же, как при отправке, поэтому с `email.reload_templates`
+/// правки видны после обновления страницы.
+#[instrument(name = "Previewing email template", skip(req, parameters, settings))]
+#[get("/emails/{template}")]
+pub async fn preview_email(
+    req: HttpRequest,
+    template: Path<String>,
+    parameters: ValidatedQuery<PreviewParameters>,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    let locale = parameters
+        .locale
+        .as_deref()
+        .and_then(Locale::from_tag)
+        .unwrap_or_else(|| request_locale(&req));
+
+    let vars = match preview_context(&template, &settings) {
+        Some(vars) => vars,
+        None => {
+            return HttpResponse::NotFound().json(ErrorResponse {
+                error: t(locale, "error.email_template_not_found"),
+            });
+        }
+    };
+
+    match render_email(&template, locale, vars, &settings) {
+        Ok(email) => match parameters.format {
+            PreviewFormat::Html => email_html_response(email.html),
+            PreviewFormat::Text => HttpResponse::Ok()
+                .content_type(ContentType::plaintext())
+                .body(format!("Subject: {}\n\n{}", email.subject, email.text)),
+        },
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to render email template {}: {}", template, e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: t(locale, "error.unexpected"),
+            })
+        }
+    }
+}
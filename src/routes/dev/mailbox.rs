@@ -0,0 +1,193 @@
+use crate::i18n::{request_locale, t};
+use crate::routes::dev::email_html_response;
+use crate::settings::Settings;
+use crate::types::{ErrorResponse, SuccessResponse};
+use crate::utils::{
+    captured_emails, clear_captured_emails, email_links, find_captured_email, normalize_text,
+    render_page, CapturedEmail, Normalize, ValidatedQuery,
+};
+use actix_web::http::header::{ContentType, CONTENT_SECURITY_POLICY};
+use actix_web::web::{Data, Path};
+use actix_web::{delete, get, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Страницы ящика: свои стили и фрейм с письмом с того же origin.
+const MAILBOX_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; style-src 'unsafe-inline'; frame-src 'self'; frame-ancestors 'none'";
+
+/// Письмо в JSON API ящика. `links` — ссылки из текстовой части,
+/// например ссылка подтверждения регистрации.
+#[derive(Serialize)]
+struct MailboxMessage {
+    #[serde(flatten)]
+    email: CapturedEmail,
+    links: Vec<String>,
+}
+
+impl From<CapturedEmail> for MailboxMessage {
+    fn from(email: CapturedEmail) -> Self {
+        let links = email_links(&email.text);
+        MailboxMessage { email, links }
+    }
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MailboxFilter {
+    /// Только письма этому адресату, без учёта регистра.
+    #[validate(length(min = 1, max = 254))]
+    to: Option<String>,
+}
+
+impl Normalize for MailboxFilter {
+    fn normalize(&mut self) {
+        if let Some(to) = self.to.as_mut() {
+            normalize_text(to);
+        }
+    }
+}
+
+/// Фрагмент текстовой части письма; ссылки на странице письма кликабельны.
+#[derive(Serialize)]
+struct TextPart<'a> {
+    text: &'a str,
+    href: Option<&'a str>,
+}
+
+/// Перехваченные письма, от новых к старым.
+fn newest_first(filter: &MailboxFilter) -> Vec<CapturedEmail> {
+    captured_emails()
+        .into_iter()
+        .rev()
+        .filter(|email| {
+            filter
+                .to
+                .as_deref()
+                .is_none_or(|to| email.to.eq_ignore_ascii_case(to))
+        })
+        .collect()
+}
+
+/// Делит текст на обычные фрагменты и ссылки `http(s)://`, сохраняя пробелы и переводы строк.
+fn linkify(text: &str) -> Vec<TextPart<'_>> {
+    let mut parts = Vec::new();
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let word = piece.trim_end();
+        if word.starts_with("http://") || word.starts_with("https://") {
+            parts.push(TextPart {
+                text: word,
+                href: Some(word),
+            });
+            parts.push(TextPart {
+                text: &piece[word.len()..],
+                href: None,
+            });
+        } else {
+            parts.push(TextPart {
+                text: piece,
+                href: None,
+            });
+        }
+    }
+    parts
+}
+
+fn page_response(page: Result<String, String>) -> HttpResponse {
+    match page {
+        Ok(page) => HttpResponse::Ok()
+            .content_type(ContentType::html())
+            .insert_header((CONTENT_SECURITY_POLICY, MAILBOX_CONTENT_SECURITY_POLICY))
+            .body(page),
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to render mailbox page: {}", e);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+fn email_not_found(req: &HttpRequest) -> HttpResponse {
+    HttpResponse::NotFound().json(ErrorResponse {
+        error: t(request_locale(req), "error.email_not_found"),
+    })
+}
+
+/// Список перехваченных писем со ссылками на каждое.
+#[instrument(name = "Showing dev mailbox", skip(filter, settings))]
+#[get("/mailbox")]
+pub async fn mailbox_page(
+    filter: ValidatedQuery<MailboxFilter>,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    page_response(render_page(
+        "dev/mailbox.html",
+        minijinja::context! { emails => newest_first(&filter) },
+        &settings,
+    ))
+}
+
+/// Письмо целиком: заголовки, ссылки, HTML во фрейме и текстовая часть.
+#[instrument(name = "Showing dev mailbox message", skip(req, settings))]
+#[get("/mailbox/{id}")]
+pub async fn message_page(
+    req: HttpRequest,
+    id: Path<Uuid>,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    let email = match find_captured_email(*id) {
+        Some(email) => email,
+        None => return email_not_found(&req),
+    };
+
+    page_response(render_page(
+        "dev/message.html",
+        minijinja::context! {
+            links => email_links(&email.text),
+            text => linkify(&email.text),
+            email => &email,
+        },
+        &settings,
+    ))
+}
+
+/// HTML-часть письма для фрейма на странице письма.
+#[instrument(name = "Showing dev mailbox message HTML", skip(req))]
+#[get("/mailbox/{id}/html")]
+pub async fn mailbox_message_html(req: HttpRequest, id: Path<Uuid>) -> HttpResponse {
+    match find_captured_email(*id) {
+        Some(email) => email_html_response(email.html),
+        None => email_not_found(&req),
+    }
+}
+
+/// Письма в JSON, от новых к старым. Тесты и локальный фронтенд находят здесь
+/// ссылку подтверждения: `GET /dev/mailbox/api/messages?to=user@example.com`.
+#[instrument(name = "Listing dev mailbox messages", skip(filter))]
+#[get("/mailbox/api/messages")]
+pub async fn mailbox_messages(filter: ValidatedQuery<MailboxFilter>) -> HttpResponse {
+    let messages: Vec<MailboxMessage> = newest_first(&filter)
+        .into_iter()
+        .map(MailboxMessage::from)
+        .collect();
+    HttpResponse::Ok().json(messages)
+}
+
+#[instrument(name = "Getting dev mailbox message", skip(req))]
+#[get("/mailbox/api/messages/{id}")]
+pub async fn mailbox_message(req: HttpRequest, id: Path<Uuid>) -> HttpResponse {
+    match find_captured_email(*id) {
+        Some(email) => HttpResponse::Ok().json(MailboxMessage::from(email)),
+        None => email_not_found(&req),
+    }
+}
+
+#[instrument(name = "Clearing dev mailbox", skip(req))]
+#[delete("/mailbox/api/messages")]
+pub async fn clear_mailbox(req: HttpRequest) -> HttpResponse {
+    clear_captured_emails();
+    tracing::event!(target: "backend", tracing::Level::INFO, "Dev mailbox cleared");
+    HttpResponse::Ok().json(SuccessResponse {
+        message: t(request_locale(&req), "success.mailbox_cleared"),
+    })
+}
@@ -0,0 +1,44 @@
+use crate::routes::dev::emails::preview_email;
+use crate::routes::dev::mailbox::{
+    clear_mailbox, mailbox_message, mailbox_message_html, mailbox_messages, mailbox_page,
+    message_page,
+};
+use crate::utils::query_config;
+use actix_web::http::header::{ContentType, CONTENT_SECURITY_POLICY, X_FRAME_OPTIONS};
+use actix_web::web::{scope, ServiceConfig};
+use actix_web::HttpResponse;
+
+mod emails;
+mod mailbox;
+
+/// Письмо показывается как есть: встроенные стили и картинки по HTTPS, но без скриптов.
+/// Заменяет общую CSP, которая запрещает всё; встраивать можно только в страницы `/dev`.
+const EMAIL_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; style-src 'unsafe-inline'; img-src https: data:; frame-ancestors 'self'";
+
+/// Инструменты разработчика под `/dev`. Подключаются только при `debug`
+/// и не входят в документацию API.
+pub fn dev_routes_config(cfg: &mut ServiceConfig, debug: bool) {
+    if debug {
+        cfg.service(
+            scope("/dev")
+                .app_data(query_config())
+                .service(preview_email)
+                .service(mailbox_page)
+                .service(mailbox_messages)
+                .service(clear_mailbox)
+                .service(mailbox_message)
+                .service(message_page)
+                .service(mailbox_message_html),
+        );
+    }
+}
+
+/// HTML письма, который можно открыть напрямую или во фрейме страницы `/dev/mailbox`.
+fn email_html_response(html: String) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header((CONTENT_SECURITY_POLICY, EMAIL_CONTENT_SECURITY_POLICY))
+        .insert_header((X_FRAME_OPTIONS, "SAMEORIGIN"))
+        .body(html)
+}
@@ -1,11 +1,188 @@
+use crate::settings::{get_settings, EmailSettings, EmailTransport};
+use crate::startup::MIGRATOR;
+use crate::types::{DependencyCheck, HealthStatus, ReadinessReport, SuccessResponse};
+use actix_web::web::Data;
 use actix_web::HttpResponse;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::future::Future;
+use std::time::{Duration, Instant};
 
+/// Оставлен для обратной совместимости, всегда отвечает 200.
+/// Для проверок оркестратора используйте `/health/live` и `/health/ready`.
 #[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/health_check/",
+    tag = "health",
+    responses((status = 200, description = "Application is up", body = SuccessResponse))
+)]
 #[actix_web::get("/health_check/")]
 pub async fn health_check() -> HttpResponse {
     tracing::event!(target: "backend", tracing::Level::INFO,
         "Accessing health-check endpoint (Доступ к конечной точке проверки работоспособности).");
-    HttpResponse::Ok().json(crate::types::SuccessResponse {
+    HttpResponse::Ok().json(SuccessResponse {
         message: "Application is safe and healthy.".to_string(),
     })
 }
+
+/// Проверка живости: процесс запущен и обрабатывает запросы. Зависимости не проверяются.
+#[tracing::instrument]
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    responses((status = 200, description = "Process is alive", body = SuccessResponse))
+)]
+#[actix_web::get("/health/live")]
+pub async fn health_live() -> HttpResponse {
+    HttpResponse::Ok().json(SuccessResponse {
+        message: "Application is alive.".to_string(),
+    })
+}
+
+/// Проверка готовности: запрос к БД, PING в Redis, отсутствие непримененных миграций
+/// и, если включено в настройках, подключение к SMTP. Каждая проверка ограничена
+/// `health.timeout_ms`. Если хоть одна не прошла, ответ 503.
+#[tracing::instrument(skip(pool, redis_pool))]
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are available", body = ReadinessReport),
+        (status = 503, description = "At least one dependency is unavailable", body = ReadinessReport),
+    )
+)]
+#[actix_web::get("/health/ready")]
+pub async fn health_ready(
+    pool: Data<PgPool>,
+    redis_pool: Data<deadpool_redis::Pool>,
+) -> HttpResponse {
+    let settings = get_settings().expect("Failed to read settings.");
+    let timeout = Duration::from_millis(settings.health.timeout_ms);
+
+    let check_smtp = settings.health.check_smtp && settings.email.transport == EmailTransport::Smtp;
+    let (database, redis, migrations, smtp) = tokio::join!(
+        run_check(timeout, check_database(&pool)),
+        run_check(timeout, check_redis(&redis_pool)),
+        run_check(timeout, check_migrations(&pool)),
+        async {
+            if check_smtp {
+                run_check(timeout, check_smtp_connection(&settings.email)).await
+            } else {
+                DependencyCheck {
+                    status: HealthStatus::Skipped,
+                    duration_ms: 0,
+                    error: None,
+                }
+            }
+        },
+    );
+
+    let checks = [
+        ("database", database),
+        ("redis", redis),
+        ("migrations", migrations),
+        ("smtp", smtp),
+    ]
+    .into_iter()
+    .map(|(name, check)| (name.to_string(), check))
+    .collect::<std::collections::BTreeMap<_, _>>();
+
+    let ready = checks
+        .values()
+        .all(|check| check.status != HealthStatus::Error);
+    let report = ReadinessReport {
+        status: if ready {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Error
+        },
+        checks,
+    };
+
+    if ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        tracing::event!(target: "backend", tracing::Level::WARN, "Readiness check failed: {:?}", report);
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+async fn run_check<F>(timeout: Duration, check: F) -> DependencyCheck
+where
+    F: Future<Output = Result<(), String>>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("Timed out after {} ms", timeout.as_millis())));
+
+    DependencyCheck {
+        status: if result.is_ok() {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Error
+        },
+        duration_ms: started.elapsed().as_millis(),
+        error: result.err(),
+    }
+}
+
+async fn check_database(pool: &PgPool) -> Result<(), String> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+async fn check_redis(redis_pool: &deadpool_redis::Pool) -> Result<(), String> {
+    let mut connection = redis_pool.get().await.map_err(|e| e.to_string())?;
+    deadpool_redis::redis::cmd("PING")
+        .query_async::<_, String>(&mut connection)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Сравнивает встроенные миграции с записями `_sqlx_migrations`.
+async fn check_migrations(pool: &PgPool) -> Result<(), String> {
+    let applied: Vec<i64> =
+        sqlx::query("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .map(|row: PgRow| row.get("version"))
+            .fetch_all(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    let pending = MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .filter(|migration| !applied.contains(&migration.version))
+        .count();
+
+    if pending == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} pending migration(s)", pending))
+    }
+}
+
+async fn check_smtp_connection(settings: &EmailSettings) -> Result<(), String> {
+    let creds = lettre::transport::smtp::authentication::Credentials::new(
+        settings.host_user.clone(),
+        settings.host_user_password.clone(),
+    );
+    let mailer: lettre::AsyncSmtpTransport<lettre::Tokio1Executor> =
+        lettre::AsyncSmtpTransport::<lettre::Tokio1Executor>::relay(&settings.host)
+            .map_err(|e| e.to_string())?
+            .credentials(creds)
+            .build();
+
+    match mailer.test_connection().await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("SMTP server did not accept the connection".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
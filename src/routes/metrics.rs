@@ -0,0 +1,39 @@
+use crate::metrics::METRICS;
+use crate::settings::Settings;
+use crate::types::ErrorResponse;
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse};
+use sqlx::PgPool;
+use subtle::ConstantTimeEq;
+
+/// Метрики в текстовом формате Prometheus.
+/// Если в настройках задан `metrics.bearer_token`, запрос должен содержать
+/// заголовок `Authorization: Bearer <token>`.
+#[tracing::instrument(skip(req, pool, redis_pool, settings))]
+#[actix_web::get("/metrics")]
+pub async fn prometheus_metrics(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    redis_pool: Data<deadpool_redis::Pool>,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    if let Some(expected) = &settings.metrics.bearer_token {
+        let provided = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or("");
+        if !bool::from(provided.as_bytes().ct_eq(expected.as_bytes())) {
+            tracing::event!(target: "backend", tracing::Level::WARN, "Rejected unauthorized metrics scrape");
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: "A valid bearer token is required to read metrics.".to_string(),
+            });
+        }
+    }
+
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(METRICS.render(&pool, &redis_pool))
+}
@@ -1,6 +1,15 @@
+mod dev;
 mod health;
+mod metrics;
+mod openapi;
 mod users;
 
-pub use health::health_check;
+pub use dev::dev_routes_config;
+
+pub use health::{health_check, health_live, health_ready};
+
+pub use metrics::prometheus_metrics;
+
+pub use openapi::{api_doc, openapi_json};
 
 pub use users::auth_routes_config;
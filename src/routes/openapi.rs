@@ -0,0 +1,65 @@
+use crate::routes::health;
+use crate::routes::users::UsersApi;
+use crate::types::{
+    AuthEvent, AuthEventPage, CodedErrorResponse, ConfirmationResponse, CsrfTokenResponse,
+    DependencyCheck, ErrorResponse, FieldError, HealthStatus, ReadinessReport, SuccessResponse,
+    UserVisible, ValidationErrorResponse,
+};
+use actix_web::HttpResponse;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(health::health_check, health::health_live, health::health_ready),
+    components(schemas(
+        ErrorResponse,
+        SuccessResponse,
+        CsrfTokenResponse,
+        CodedErrorResponse,
+        ConfirmationResponse,
+        FieldError,
+        ValidationErrorResponse,
+        UserVisible,
+        AuthEvent,
+        AuthEventPage,
+        HealthStatus,
+        DependencyCheck,
+        ReadinessReport,
+    )),
+    modifiers(&SessionCookie),
+    tags(
+        (name = "users", description = "Registration, activation, login and logout"),
+        (name = "auth events", description = "Authentication audit log"),
+        (name = "health", description = "Service health"),
+    )
+)]
+struct ApiDoc;
+
+/// Сессия хранится в cookie `id` (имя задаётся в `session.cookie_name`, в production — `__Host-id`),
+/// которую выставляет `SessionMiddleware` при входе.
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "session",
+                SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("id"))),
+            );
+        }
+    }
+}
+
+/// Полный документ OpenAPI 3 по всем маршрутам приложения.
+pub fn api_doc() -> utoipa::openapi::OpenApi {
+    let mut doc = ApiDoc::openapi();
+    doc.merge(UsersApi::openapi());
+    doc
+}
+
+#[tracing::instrument]
+#[actix_web::get("/openapi.json")]
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(api_doc())
+}
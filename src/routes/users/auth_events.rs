@@ -0,0 +1,221 @@
+use crate::i18n::{request_locale, t, Locale};
+use crate::types::{AuthEvent, AuthEventKind, AuthEventOutcome, AuthEventPage, ErrorResponse};
+use crate::utils::{
+    normalize_text, session_user_id, session_user_is_admin, Normalize, ValidatedQuery,
+};
+use actix_session::Session;
+use actix_web::web::Data;
+use actix_web::{get, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use sqlx::postgres::PgRow;
+use sqlx::{Error, PgPool, Row};
+use tracing::instrument;
+use utoipa::IntoParams;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+/// Фильтр журнала событий. Все поля необязательные.
+#[derive(Deserialize, Debug, Default, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuthEventFilter {
+    user_id: Option<Uuid>,
+    #[validate(custom = "validate_event_type")]
+    event_type: Option<String>,
+    #[validate(custom = "validate_outcome")]
+    outcome: Option<String>,
+    #[validate(length(max = 45))]
+    ip_address: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    #[validate(range(min = 1))]
+    page: Option<i64>,
+    #[validate(range(min = 1, max = 100))]
+    page_size: Option<i64>,
+}
+
+impl Normalize for AuthEventFilter {
+    fn normalize(&mut self) {
+        for value in [
+            &mut self.event_type,
+            &mut self.outcome,
+            &mut self.ip_address,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            normalize_text(value);
+        }
+    }
+}
+
+fn validate_event_type(event_type: &str) -> Result<(), ValidationError> {
+    serde_json::from_value::<AuthEventKind>(serde_json::json!(event_type))
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("invalid_choice"))
+}
+
+fn validate_outcome(outcome: &str) -> Result<(), ValidationError> {
+    serde_json::from_value::<AuthEventOutcome>(serde_json::json!(outcome))
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("invalid_choice"))
+}
+
+impl AuthEventFilter {
+    fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    fn page_size(&self) -> i64 {
+        self.page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/// История событий аутентификации текущего пользователя.
+#[instrument(name = "Getting own auth events", skip(req, pool, session, filter))]
+#[utoipa::path(
+    get,
+    path = "/users/me/events/",
+    tag = "auth events",
+    params(AuthEventFilter),
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "Page of the current user's events", body = AuthEventPage),
+        (status = 400, description = "Invalid filter, in the `ValidationErrorResponse` format",
+            body = ErrorResponse),
+        (status = 401, description = "Not logged in", body = ErrorResponse),
+    )
+)]
+#[get("/me/events/")]
+pub async fn user_auth_events(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    session: Session,
+    filter: ValidatedQuery<AuthEventFilter>,
+) -> HttpResponse {
+    let locale = request_locale(&req);
+    let user_id = match session_user_id(&session).await {
+        Ok(id) => id,
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to get user from session: {:#?}", e);
+            return HttpResponse::Unauthorized().json(ErrorResponse {
+                error: t(locale, "error.not_logged_in"),
+            });
+        }
+    };
+
+    // Пользователь видит только свои события, какой бы `user_id` он ни передал.
+    let filter = AuthEventFilter {
+        user_id: Some(user_id),
+        ..filter.into_inner()
+    };
+
+    auth_events_response(&pool, &filter, locale).await
+}
+
+/// Поиск по журналу событий для администраторов (`is_staff` или `is_superuser`).
+#[instrument(name = "Searching auth events", skip(req, pool, session, filter))]
+#[utoipa::path(
+    get,
+    path = "/users/admin/events/",
+    tag = "auth events",
+    params(AuthEventFilter),
+    security(("session" = [])),
+    responses(
+        (status = 200, description = "Page of matching events", body = AuthEventPage),
+        (status = 400, description = "Invalid filter, in the `ValidationErrorResponse` format",
+            body = ErrorResponse),
+        (status = 403, description = "Not a staff member or superuser", body = ErrorResponse),
+    )
+)]
+#[get("/admin/events/")]
+pub async fn admin_auth_events(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    session: Session,
+    filter: ValidatedQuery<AuthEventFilter>,
+) -> HttpResponse {
+    let locale = request_locale(&req);
+    if let Err(e) = session_user_is_admin(&session, &pool).await {
+        tracing::event!(target: "backend", tracing::Level::ERROR, "Access to auth events denied: {:#?}", e);
+        return HttpResponse::Forbidden().json(ErrorResponse {
+            error: t(locale, "error.permission_denied"),
+        });
+    }
+
+    auth_events_response(&pool, &filter, locale).await
+}
+
+async fn auth_events_response(
+    pool: &PgPool,
+    filter: &AuthEventFilter,
+    locale: Locale,
+) -> HttpResponse {
+    match search_auth_events(pool, filter).await {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => {
+            tracing::event!(target: "sqlx", tracing::Level::ERROR, "Failed to fetch auth events: {:#?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: t(locale, "error.unexpected"),
+            })
+        }
+    }
+}
+
+#[instrument(name = "Fetching auth events from DB.", skip(pool))]
+pub async fn search_auth_events(
+    pool: &PgPool,
+    filter: &AuthEventFilter,
+) -> Result<AuthEventPage, Error> {
+    let page = filter.page();
+    let page_size = filter.page_size();
+
+    let rows = sqlx::query(
+        "SELECT id, user_id, event_type, outcome, ip_address, user_agent, details, created_at, \
+        COUNT(*) OVER() AS total FROM auth_events \
+        WHERE ($1::UUID IS NULL OR user_id = $1) \
+        AND ($2::TEXT IS NULL OR event_type = $2) \
+        AND ($3::TEXT IS NULL OR outcome = $3) \
+        AND ($4::TEXT IS NULL OR ip_address = $4) \
+        AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5) \
+        AND ($6::TIMESTAMPTZ IS NULL OR created_at < $6) \
+        ORDER BY created_at DESC LIMIT $7 OFFSET $8",
+    )
+    .bind(filter.user_id)
+    .bind(&filter.event_type)
+    .bind(&filter.outcome)
+    .bind(&filter.ip_address)
+    .bind(filter.from)
+    .bind(filter.to)
+    .bind(page_size)
+    .bind((page - 1) * page_size)
+    .fetch_all(pool)
+    .await?;
+
+    let total = rows.first().map_or(0, |row: &PgRow| row.get("total"));
+    let events = rows
+        .into_iter()
+        .map(|row: PgRow| AuthEvent {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            event_type: row.get("event_type"),
+            outcome: row.get("outcome"),
+            ip_address: row.get("ip_address"),
+            user_agent: row.get("user_agent"),
+            details: row.get("details"),
+            created_at: row.get("created_at"),
+        })
+        .collect();
+
+    Ok(AuthEventPage {
+        events,
+        page,
+        page_size,
+        total,
+    })
+}
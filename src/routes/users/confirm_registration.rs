@@ -1,96 +1,248 @@
-use crate::settings::get_settings;
-use crate::types::{ErrorResponse, SuccessResponse};
-use crate::utils::verify_confirmation_token_pasetor;
-use actix_web::http::header::LOCATION;
-use actix_web::web::{Data, Query};
-use actix_web::{get, HttpResponse};
+use crate::i18n::{request_locale, t, Locale};
+use crate::settings::{get_settings, Settings};
+use crate::types::{
+    AuthEventKind, CodedErrorResponse, ConfirmationResponse, ErrorResponse, NewAuthEvent,
+};
+use crate::utils::{
+    is_allowed_redirect, normalize_text, record_auth_event, resolve_frontend_url,
+    verify_confirmation_token_pasetor, Normalize, ValidatedQuery,
+};
+use actix_web::http::header::{ACCEPT, LOCATION};
+use actix_web::http::StatusCode;
+use actix_web::web::Data;
+use actix_web::{get, HttpRequest, HttpResponse};
 use deadpool_redis::Pool;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sqlx::{Error, PgPool};
 use tracing::instrument;
+use utoipa::IntoParams;
 use uuid::Uuid;
+use validator::Validate;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Parameters {
+    /// Токен из ссылки в письме подтверждения.
+    #[validate(length(min = 1, max = 4096))]
     token: String,
 }
 
-#[instrument(name = "Activating a new user", skip(pool, parameters, redis_pool))]
+impl Normalize for Parameters {
+    fn normalize(&mut self) {
+        normalize_text(&mut self.token);
+    }
+}
+
+#[instrument(
+    name = "Activating a new user",
+    skip(req, pool, parameters, redis_pool)
+)]
+#[utoipa::path(
+    get,
+    path = "/users/register/confirm/",
+    tag = "users",
+    params(Parameters),
+    responses(
+        (status = 200, description = "Account activated; returned instead of the redirect \
+        for `Accept: application/json`", body = ConfirmationResponse),
+        (status = 303, description = "Redirect to the frontend: the `next` address from the \
+        token or `confirmation.success_url` on success, `confirmation.invalid_token_url` for an \
+        invalid or used token, `confirmation.error_url` otherwise"),
+        (status = 400, description = "Missing or malformed token in the \
+        `ValidationErrorResponse` format, or, for `Accept: application/json`, an invalid or \
+        used token with code `invalid_token`", body = CodedErrorResponse),
+        (status = 500, description = "For `Accept: application/json`: the account could not \
+        be activated, code `activation_failed`", body = CodedErrorResponse),
+        (status = 503, description = "For `Accept: application/json`: Redis is unavailable, \
+        code `service_unavailable`", body = CodedErrorResponse),
+    )
+)]
 #[get("/register/confirm/")]
 pub async fn confirm(
-    parameters: Query<Parameters>,
+    req: HttpRequest,
+    parameters: ValidatedQuery<Parameters>,
     pool: Data<PgPool>,
     redis_pool: Data<Pool>,
 ) -> HttpResponse {
     let settings = get_settings().expect("Failed to read settings.");
+    let wants_json = accepts_json(&req);
+    let locale = request_locale(&req);
 
-    let mut redis_con = redis_pool
-        .get()
-        .await
-        .map_err(|e| {
-            tracing::event!(target: "backend", tracing::Level::ERROR, "{}", e);
-
-            HttpResponse::SeeOther()
-                .insert_header((LOCATION, format!("{}/auth/error", settings.frontend_url)))
-                .json(ErrorResponse {
-                    error: "We cannot activate your account at the moment".to_string(),
-                })
-        })
-        .expect("Redis connection cannot be gotten.");
-
-    let confirmation_token = match verify_confirmation_token_pasetor(
-        parameters.token.clone(),
-        &mut redis_con,
-        None,
-    )
-    .await
-    {
-        Ok(token) => token,
+    let mut redis_con = match redis_pool.get().await {
+        Ok(redis_con) => redis_con,
         Err(e) => {
-            tracing::event!(target: "backend", tracing::Level::ERROR, "{:#?}", e);
-
-            return HttpResponse::SeeOther()
-                .insert_header((
-                    LOCATION,
-                    format!("{}/auth/regenerate-token", settings.frontend_url),
-                ))
-                .json(ErrorResponse {
-                    error:
-                        "It appears that your confirmation token has expired or previously used. \
-                    Kindiy generate a new token"
-                            .to_string(),
-                });
+            tracing::event!(target: "backend", tracing::Level::ERROR, "{}", e);
+            return failure_response(
+                &settings,
+                wants_json,
+                locale,
+                ConfirmationFailure::ServiceUnavailable,
+            );
         }
     };
 
+    let confirmation_token =
+        match verify_confirmation_token_pasetor(parameters.token.clone(), &mut redis_con, None)
+            .await
+        {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::event!(target: "backend", tracing::Level::ERROR, "{:#?}", e);
+                record_auth_event(
+                    &pool,
+                    &req,
+                    NewAuthEvent::failure(AuthEventKind::Activation).reason("invalid_token"),
+                )
+                .await;
+                return failure_response(
+                    &settings,
+                    wants_json,
+                    locale,
+                    ConfirmationFailure::InvalidToken,
+                );
+            }
+        };
+
     match activate_new_user(&pool, confirmation_token.user_id).await {
         Ok(_) => {
             tracing::event!(target: "backend", tracing::Level::INFO, "New user was activated successfully");
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::success(AuthEventKind::Activation).user(confirmation_token.user_id),
+            )
+            .await;
+
+            // Список разрешённых адресов мог измениться с момента выдачи токена
+            let next = confirmation_token
+                .next
+                .filter(|next| is_allowed_redirect(next, &settings));
+            let body = ConfirmationResponse {
+                message: t(locale, "success.activated"),
+                next: next.clone(),
+            };
 
-            HttpResponse::SeeOther()
-                .insert_header((
-                    LOCATION,
-                    format!("{}/auth/confirmed", settings.frontend_url),
-                ))
-                .json(SuccessResponse {
-                    message: "Your account has been activated successfully! You can log in"
-                        .to_string(),
-                })
+            if wants_json {
+                return HttpResponse::Ok().json(body);
+            }
+            let url = next.unwrap_or_else(|| {
+                resolve_frontend_url(&settings.confirmation.success_url, &settings.frontend_url)
+            });
+            redirect_to_frontend(&settings, locale, url, body)
         }
 
         Err(e) => {
             tracing::event!(target: "backend", tracing::Level::ERROR, "Cannot activate account : {}", e);
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::failure(AuthEventKind::Activation)
+                    .user(confirmation_token.user_id)
+                    .reason("db_error"),
+            )
+            .await;
+            failure_response(
+                &settings,
+                wants_json,
+                locale,
+                ConfirmationFailure::ActivationFailed,
+            )
+        }
+    }
+}
+
+/// Неудачный исход подтверждения и его машиночитаемый код.
+enum ConfirmationFailure {
+    InvalidToken,
+    ServiceUnavailable,
+    ActivationFailed,
+}
+
+impl ConfirmationFailure {
+    fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidToken => "invalid_token",
+            Self::ServiceUnavailable => "service_unavailable",
+            Self::ActivationFailed => "activation_failed",
+        }
+    }
 
-            HttpResponse::SeeOther()
-                .insert_header((
-                    LOCATION,
-                    format!("{}/auth/error?reason={e}", settings.frontend_url),
-                ))
-                .json(ErrorResponse {
-                    error: "We cannot activate your account at the moment".to_string(),
-                })
+    fn message(&self, locale: Locale) -> String {
+        match self {
+            Self::InvalidToken => t(locale, "error.invalid_token"),
+            Self::ServiceUnavailable | Self::ActivationFailed => {
+                t(locale, "error.activation_unavailable")
+            }
         }
     }
+
+    fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidToken => StatusCode::BAD_REQUEST,
+            Self::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::ActivationFailed => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// JSON с кодом ошибки для API-клиентов, иначе редирект на страницу ошибки фронтенда
+/// с тем же кодом в параметре `reason`.
+fn failure_response(
+    settings: &Settings,
+    wants_json: bool,
+    locale: Locale,
+    failure: ConfirmationFailure,
+) -> HttpResponse {
+    let body = CodedErrorResponse {
+        base: ErrorResponse {
+            error: failure.message(locale),
+        },
+        code: failure.code().to_string(),
+    };
+    if wants_json {
+        return HttpResponse::build(failure.status()).json(body);
+    }
+
+    let target = match failure {
+        ConfirmationFailure::InvalidToken => &settings.confirmation.invalid_token_url,
+        _ => &settings.confirmation.error_url,
+    };
+    let url = resolve_frontend_url(target, &settings.frontend_url);
+    let separator = if url.contains('?') { '&' } else { '?' };
+    redirect_to_frontend(
+        settings,
+        locale,
+        format!("{}{}reason={}", url, separator, failure.code()),
+        body,
+    )
+}
+
+/// Клиент просит JSON вместо редиректа (`Accept: application/json`).
+fn accepts_json(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("application/json"))
+}
+
+/// Редирект 303 на фронтенд. Адрес, чей origin не разрешён настройками `cors`,
+/// не отдаётся клиенту: это ошибка конфигурации, а не открытый редирект.
+fn redirect_to_frontend<T: Serialize>(
+    settings: &Settings,
+    locale: Locale,
+    url: String,
+    body: T,
+) -> HttpResponse {
+    if !is_allowed_redirect(&url, settings) {
+        tracing::event!(target: "backend", tracing::Level::ERROR, "Refusing to redirect to {}: origin is not allowed by the CORS settings", url);
+        return HttpResponse::InternalServerError().json(ErrorResponse {
+            error: t(locale, "error.unexpected"),
+        });
+    }
+
+    HttpResponse::SeeOther()
+        .insert_header((LOCATION, url))
+        .json(body)
 }
 
 #[instrument(name = "Mark a user active", skip(pool),
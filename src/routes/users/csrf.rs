@@ -0,0 +1,31 @@
+use crate::i18n::{request_locale, t};
+use crate::types::{CsrfTokenResponse, ErrorResponse};
+use crate::utils::session_csrf_token;
+use actix_session::Session;
+use actix_web::{get, HttpRequest, HttpResponse};
+use tracing::instrument;
+
+/// CSRF-токен текущей сессии для заголовка `X-CSRF-Token`.
+/// После входа выдаётся новый токен, поэтому его нужно запросить заново.
+#[instrument(name = "Getting CSRF token", skip(req, session))]
+#[utoipa::path(
+    get,
+    path = "/users/csrf-token/",
+    tag = "users",
+    responses(
+        (status = 200, description = "CSRF token bound to the session", body = CsrfTokenResponse),
+        (status = 500, description = "Session cannot be read", body = ErrorResponse),
+    )
+)]
+#[get("/csrf-token/")]
+pub async fn csrf_token(req: HttpRequest, session: Session) -> HttpResponse {
+    match session_csrf_token(&session) {
+        Ok(csrf_token) => HttpResponse::Ok().json(CsrfTokenResponse { csrf_token }),
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to issue CSRF token: {:#?}", e);
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: t(request_locale(&req), "error.unexpected"),
+            })
+        }
+    }
+}
@@ -1,78 +1,223 @@
-use crate::types::{ErrorResponse, User, UserVisible, USER_EMAIL_KEY, USER_ID_KEY};
-use crate::utils::verify_password;
+use crate::i18n::{request_locale, t, Locale};
+use crate::settings::{PasswordHashingSettings, Settings};
+use crate::types::{
+    AuthEventKind, ErrorResponse, NewAuthEvent, User, UserVisible, CSRF_TOKEN_KEY, USER_EMAIL_KEY,
+    USER_ID_KEY, USER_IS_STAFF_KEY, USER_IS_SUPERUSER, USER_LOCALE_KEY,
+};
+use crate::utils::{
+    hash, needs_rehash, normalize_email, normalize_text, record_auth_event, spawn_blocking_in_span,
+    verify_dummy_password, verify_password, Normalize, ValidatedJson,
+};
 use actix_session::Session;
-use actix_web::web::{Data, Json};
-use actix_web::{post, HttpResponse};
+use actix_web::web::Data;
+use actix_web::{post, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::{query, Error, PgPool, Row};
-use tokio::task::spawn_blocking;
 use tracing::instrument;
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
 pub struct LoginUser {
+    #[validate(email, length(max = 254))]
     email: String,
+    #[validate(length(min = 1, max = 1024))]
     password: String,
 }
 
-#[instrument(name = "Logging a user in", skip( pool, user, session), fields(user_email = %user.email))]
+impl Normalize for LoginUser {
+    fn normalize(&mut self) {
+        normalize_text(&mut self.email);
+    }
+}
+
+#[instrument(name = "Logging a user in", skip(req, pool, user, session, settings), fields(user_email = %user.email))]
+#[utoipa::path(
+    post,
+    path = "/users/login/",
+    tag = "users",
+    request_body = LoginUser,
+    responses(
+        (status = 200, description = "Logged in, session cookie set", body = UserVisible),
+        (status = 400, description = "Wrong credentials, or an invalid payload \
+        (then in the `ValidationErrorResponse` format). With `security.anti_enumeration` \
+        every failed login gets the same response", body = ErrorResponse),
+        (status = 404, description = "No active user with this email; only when \
+        `security.anti_enumeration` is off", body = ErrorResponse),
+    )
+)]
 #[post("/login/")]
-async fn login_user(pool: Data<PgPool>, user: Json<LoginUser>, session: Session) -> HttpResponse {
-    match get_user_who_is_active(&pool, &user.email).await {
-        Ok(loggedin_user) => match spawn_blocking(move || {
-            verify_password(loggedin_user.password.as_ref(), user.password.as_bytes())
-        })
-        .await
-        .expect("Unable to unwrap JoinError.")
-        {
-            Ok(_) => {
-                tracing::event!(target: "backend", tracing::Level::INFO, "User logged in successfully");
-                session.renew();
-                session
-                    .insert(USER_ID_KEY, loggedin_user.id)
-                    .expect("'user_id' cannot be inserted into session");
-                session
-                    .insert(USER_EMAIL_KEY, &loggedin_user.email)
-                    .expect("'user_email' cannot be inserted into session");
+async fn login_user(
+    req: HttpRequest,
+    pool: Data<PgPool>,
+    user: ValidatedJson<LoginUser>,
+    session: Session,
+    settings: Data<Settings>,
+) -> HttpResponse {
+    let locale = request_locale(&req);
 
-                HttpResponse::Ok().json(UserVisible {
-                    id: loggedin_user.id,
-                    email: loggedin_user.email,
-                    first_name: loggedin_user.first_name,
-                    last_name: loggedin_user.last_name,
-                    is_active: loggedin_user.is_active,
-                    is_staff: loggedin_user.is_staff,
-                    is_superuser: loggedin_user.is_superuser,
-                    thumbnail: loggedin_user.thumbnail,
-                    date_joined: loggedin_user.date_joined,
-                })
-            }
-            Err(e) => {
-                tracing::event!(target: "argon2", tracing::Level::ERROR, "Failed to authenticate user: \
-                {:#?}", e);
-                HttpResponse::BadRequest().json(ErrorResponse {
-                    error: "Email and password do not match.".to_string(),
-                })
+    // Невалидный адрес ищем как есть: такой учётной записи всё равно нет.
+    let email = normalize_email(&user.email, &settings.email_normalization)
+        .map(|email| email.canonical)
+        .unwrap_or_else(|_| user.email.to_lowercase());
+
+    match get_user_who_is_active(&pool, &email).await {
+        Ok(loggedin_user) => {
+            let password_hash = loggedin_user.password.clone();
+            let password = user.password.clone();
+            let hashing = settings.clone();
+            match spawn_blocking_in_span(move || {
+                verify_password(
+                    password_hash.as_ref(),
+                    user.password.as_bytes(),
+                    &hashing.password_hashing,
+                )
+            })
+            .await
+            .expect("Unable to unwrap JoinError.")
+            {
+                Ok(_) => {
+                    tracing::event!(target: "backend", tracing::Level::INFO, "User logged in successfully");
+                    if needs_rehash(&loggedin_user.password, &settings.password_hashing) {
+                        rehash_user_password(
+                            &pool,
+                            loggedin_user.id,
+                            &password,
+                            &settings.password_hashing,
+                        )
+                        .await;
+                    }
+                    // Новый ключ сессии и новый CSRF-токен: старые могли быть известны атакующему
+                    session.renew();
+                    session.remove(CSRF_TOKEN_KEY);
+                    session
+                        .insert(USER_ID_KEY, loggedin_user.id)
+                        .expect("'user_id' cannot be inserted into session");
+                    session
+                        .insert(USER_EMAIL_KEY, &loggedin_user.email)
+                        .expect("'user_email' cannot be inserted into session");
+                    session
+                        .insert(USER_IS_STAFF_KEY, loggedin_user.is_staff)
+                        .expect("'user_is_staff' cannot be inserted into session");
+                    session
+                        .insert(USER_IS_SUPERUSER, loggedin_user.is_superuser)
+                        .expect("'user_is_superuser' cannot be inserted into session");
+                    // Язык из учётной записи важнее `Accept-Language` до конца сессии.
+                    if let Some(user_locale) =
+                        loggedin_user.locale.as_deref().and_then(Locale::from_tag)
+                    {
+                        session
+                            .insert(USER_LOCALE_KEY, user_locale)
+                            .expect("'user_locale' cannot be inserted into session");
+                    }
+
+                    record_auth_event(
+                        &pool,
+                        &req,
+                        NewAuthEvent::success(AuthEventKind::Login).user(loggedin_user.id),
+                    )
+                    .await;
+
+                    HttpResponse::Ok().json(UserVisible {
+                        id: loggedin_user.id,
+                        email: loggedin_user.email,
+                        first_name: loggedin_user.first_name,
+                        last_name: loggedin_user.last_name,
+                        is_active: loggedin_user.is_active,
+                        is_staff: loggedin_user.is_staff,
+                        is_superuser: loggedin_user.is_superuser,
+                        thumbnail: loggedin_user.thumbnail,
+                        date_joined: loggedin_user.date_joined,
+                    })
+                }
+                Err(e) => {
+                    tracing::event!(target: "argon2", tracing::Level::ERROR, "Failed to authenticate user: \
+                    {:#?}", e);
+                    record_auth_event(
+                        &pool,
+                        &req,
+                        NewAuthEvent::failure(AuthEventKind::Login)
+                            .user(loggedin_user.id)
+                            .reason("wrong_password"),
+                    )
+                    .await;
+                    if settings.security.anti_enumeration {
+                        return invalid_credentials_response(locale);
+                    }
+                    HttpResponse::BadRequest().json(ErrorResponse {
+                        error: t(locale, "error.credentials_mismatch"),
+                    })
+                }
             }
-        },
+        }
         Err(e) => {
             tracing::event!(target: "sqlx", tracing::Level::ERROR, "User not found: {:#?}", e);
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::failure(AuthEventKind::Login).reason("user_not_found"),
+            )
+            .await;
+            if settings.security.anti_enumeration {
+                // Выравниваем время ответа с проверкой настоящего хэша.
+                let hashing = settings.clone();
+                spawn_blocking_in_span(move || {
+                    verify_dummy_password(user.password.as_bytes(), &hashing.password_hashing)
+                })
+                .await
+                .expect("Unable to unwrap JoinError.");
+                return invalid_credentials_response(locale);
+            }
             HttpResponse::NotFound().json(ErrorResponse {
-                error: "A user with there details does not exist. If you registered with these details, \
-                ensure you activate your account by clicking on the link sent to your e-mail address"
-                    .to_string(),
+                error: t(locale, "error.user_not_found"),
             })
         }
     }
 }
 
-#[instrument(name = "Getting a user from DB.", skip(pool, email), fields(user_email = %email))]
-pub async fn get_user_who_is_active(pool: &PgPool, email: &String) -> Result<User, Error> {
+/// Единый ответ на любую неудачную попытку входа в режиме `anti_enumeration`:
+/// неизвестный email, неактивная учётная запись и неверный пароль неотличимы.
+fn invalid_credentials_response(locale: Locale) -> HttpResponse {
+    HttpResponse::BadRequest().json(ErrorResponse {
+        error: t(locale, "error.invalid_credentials"),
+    })
+}
+
+/// Пересчитывает хэш пароля с текущими параметрами после успешного входа.
+/// Ошибка только логируется: вход пользователя от неё не зависит.
+#[instrument(name = "Rehashing user password", skip(pool, password, settings))]
+async fn rehash_user_password(
+    pool: &PgPool,
+    user_id: uuid::Uuid,
+    password: &str,
+    settings: &PasswordHashingSettings,
+) {
+    let new_hash = hash(password.as_bytes(), settings).await;
+
+    match query("UPDATE users SET password = $1 WHERE id = $2")
+        .bind(new_hash)
+        .bind(user_id)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => {
+            tracing::event!(target: "backend", tracing::Level::INFO, "Password hash upgraded to current parameters");
+        }
+        Err(e) => {
+            tracing::event!(target: "sqlx", tracing::Level::ERROR, "Failed to update password hash: {:#?}", e);
+        }
+    }
+}
+
+/// `email_normalized` — канонический адрес из `normalize_email`.
+#[instrument(name = "Getting a user from DB.", skip(pool, email_normalized), fields(user_email = %email_normalized))]
+pub async fn get_user_who_is_active(pool: &PgPool, email_normalized: &str) -> Result<User, Error> {
     match query(
         "SELECT id, email, password, first_name, last_name, is_staff, is_superuser, \
-    thumbnail, date_joined FROM users WHERE email = $1 AND is_active = TRUE",
+    thumbnail, date_joined, locale FROM users WHERE email_normalized = $1 AND is_active = TRUE",
     )
-    .bind(email)
+    .bind(email_normalized)
     .map(|row: PgRow| User {
         id: row.get("id"),
         email: row.get("email"),
@@ -84,6 +229,7 @@ pub async fn get_user_who_is_active(pool: &PgPool, email: &String) -> Result<Use
         is_superuser: row.get("is_superuser"),
         thumbnail: row.get("thumbnail"),
         date_joined: row.get("date_joined"),
+        locale: row.get("locale"),
     })
     .fetch_one(pool)
     .await
@@ -91,6 +237,7 @@ pub async fn get_user_who_is_active(pool: &PgPool, email: &String) -> Result<Use
         Ok(user) => Ok(user),
         Err(e) => {
             tracing::event!(target: "sqlx", tracing::Level::ERROR, "User not found in DB: {:#?}", e);
+            Err(e)
         }
     }
-}
\ No newline at end of file
+}
@@ -1,37 +1,55 @@
+use crate::i18n::{request_locale, t};
+use crate::types::{AuthEventKind, ErrorResponse, NewAuthEvent, SuccessResponse};
+use crate::utils::{record_auth_event, session_user_id};
 use actix_session::Session;
-use actix_web::{HttpResponse, post};
+use actix_web::web::Data;
+use actix_web::{post, HttpRequest, HttpResponse};
+use sqlx::PgPool;
 use tracing::instrument;
-use uuid::Uuid;
-use crate::types::{ErrorResponse, SuccessResponse, USER_ID_KEY};
 
-#[instrument(name = "Log out user.", skip(session))]
+#[instrument(name = "Log out user.", skip(session, pool, req))]
+#[utoipa::path(
+    post,
+    path = "/users/logout/",
+    tag = "users",
+    security(("session" = [])),
+    params(
+        ("X-CSRF-Token" = String, Header, description = "Token from `GET /users/csrf-token/`"),
+    ),
+    responses(
+        (status = 200, description = "Session destroyed", body = SuccessResponse),
+        (status = 400, description = "No active session", body = ErrorResponse),
+        (status = 403, description = "Missing or invalid CSRF token", body = ErrorResponse),
+    )
+)]
 #[post("/logout/")]
-pub async fn log_out(session: Session) -> HttpResponse {
+pub async fn log_out(req: HttpRequest, pool: Data<PgPool>, session: Session) -> HttpResponse {
+    let locale = request_locale(&req);
     match session_user_id(&session).await {
-        Ok(_) => {
+        Ok(user_id) => {
             tracing::event!(target: "backend", tracing::Level::INFO, "Users retrieved from the DB.");
             session.purge();
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::success(AuthEventKind::Logout).user(user_id),
+            )
+            .await;
             HttpResponse::Ok().json(SuccessResponse {
-                message: "You have successfully logged out".to_string(),
+                message: t(locale, "success.logged_out"),
             })
         }
         Err(e) => {
             tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to get user from session: {:#?}", e);
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::failure(AuthEventKind::Logout).reason("not_authenticated"),
+            )
+            .await;
             HttpResponse::BadRequest().json(ErrorResponse {
-                error: "We currently have some issues. Kindly try again and ensure you are logged in."
-                    .to_string(),
+                error: t(locale, "error.logout_failed"),
             })
         }
     }
 }
-
-#[instrument(name = "Get user_id from session.", skip(session))]
-async fn session_user_id(session: &Session) -> Result<Uuid, String> {
-    match session.get(USER_ID_KEY) {
-        Ok(user_id) => match user_id {
-            None => Err("You are not authenticated". to_string()),
-            Some(id) => Ok(id),
-        },
-        Err(e) => Err(format!("{}", e)),
-    }
-}
\ No newline at end of file
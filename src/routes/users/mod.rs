@@ -1,20 +1,48 @@
+use crate::routes::users::auth_events::{admin_auth_events, user_auth_events};
 use crate::routes::users::confirm_registration::confirm;
-use crate::routes::users::register::register_user;
-use actix_web::web::{scope, ServiceConfig};
+use crate::routes::users::csrf::csrf_token;
 use crate::routes::users::login::login_user;
 use crate::routes::users::logout::log_out;
+use crate::routes::users::register::register_user;
+use crate::utils::{json_config, query_config};
+use actix_web::web::{scope, ServiceConfig};
+use utoipa::OpenApi;
 
+mod auth_events;
 mod confirm_registration;
+mod csrf;
 mod login;
-mod register;
 mod logout;
+mod register;
+
+/// Описание маршрутов `/users` для OpenAPI, объединяется с общим документом в `api_doc`.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        register::register_user,
+        confirm_registration::confirm,
+        login::login_user,
+        logout::log_out,
+        csrf::csrf_token,
+        auth_events::user_auth_events,
+        auth_events::admin_auth_events,
+    ),
+    components(schemas(register::NewUser, login::LoginUser))
+)]
+pub struct UsersApi;
 
 pub fn auth_routes_config(cfg: &mut ServiceConfig) {
     cfg.service(
         scope("/users")
+            // Единый формат ошибок разбора тела и строки запроса для всех маршрутов
+            .app_data(json_config())
+            .app_data(query_config())
             .service(register_user)
             .service(confirm)
             .service(login_user)
-            .service(log_out),
+            .service(log_out)
+            .service(csrf_token)
+            .service(user_auth_events)
+            .service(admin_auth_events),
     );
 }
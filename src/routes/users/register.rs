@@ -1,77 +1,223 @@
-use crate::types::ErrorResponse;
-use crate::utils::{hash, send_multipart_email};
-use actix_web::web::{Data, Json};
-use actix_web::HttpResponse;
+use crate::i18n::{request_locale, t, Locale};
+use crate::settings::Settings;
+use crate::types::{
+    AuthEventKind, ErrorResponse, FieldError, NewAuthEvent, SuccessResponse,
+    ValidationErrorResponse,
+};
+use crate::utils::{
+    hash, is_allowed_redirect, is_password_breached, normalize_email, normalize_text,
+    record_auth_event, send_multipart_email, send_notification_email, validate_password,
+    BackgroundTasks, BreachedPasswordChecker, EmailRecipient, Normalize, ValidatedJson,
+};
+use actix_web::web::Data;
+use actix_web::{HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPool, Row};
+use utoipa::ToSchema;
+use validator::Validate;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Validate, ToSchema)]
 pub struct NewUser {
+    #[validate(email, length(max = 254))]
     email: String,
+    // Остальные требования к паролю проверяет `validate_password` по политике из настроек.
+    #[validate(length(min = 1, max = 1024))]
     password: String,
+    #[validate(length(min = 1, max = 100))]
     first_name: String,
+    #[validate(length(min = 1, max = 100))]
     last_name: String,
+    /// Куда перенаправить после подтверждения; origin должен быть разрешён настройками CORS.
+    #[validate(length(min = 1, max = 2048))]
+    next: Option<String>,
+}
+
+impl Normalize for NewUser {
+    fn normalize(&mut self) {
+        normalize_text(&mut self.email);
+        normalize_text(&mut self.first_name);
+        normalize_text(&mut self.last_name);
+        if let Some(next) = self.next.as_mut() {
+            normalize_text(next);
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct CreateNewUser {
     email: String,
+    email_normalized: String,
     password: String,
     first_name: String,
     last_name: String,
+    locale: Locale,
 }
 
 #[tracing::instrument(name = "Adding a new user",
-skip(pool, new_user, redis_pool),
+skip(req, pool, new_user, redis_pool, breached_passwords, tasks, settings),
 fields(
 new_user_email = %new_user.email,
 new_user_first_name = %new_user.first_name,
 new_user_last_name = %new_user.last_name
 ))]
+#[utoipa::path(
+    post,
+    path = "/users/register/",
+    tag = "users",
+    request_body = NewUser,
+    responses(
+        (status = 200, description = "Account created, confirmation email sent. With \
+        `security.anti_enumeration` also returned for a taken email, and the owner is emailed", body = SuccessResponse),
+        (status = 400, description = "Invalid payload or password rejected by the policy", body = ValidationErrorResponse),
+        (status = 500, description = "Email already taken (only when `security.anti_enumeration` \
+        is off) or the database is unavailable", body = ErrorResponse),
+    )
+)]
 #[actix_web::post("/register/")]
 pub async fn register_user(
+    req: HttpRequest,
     pool: Data<PgPool>,
-    new_user: Json<NewUser>,
+    new_user: ValidatedJson<NewUser>,
     redis_pool: Data<deadpool_redis::Pool>,
+    breached_passwords: Data<BreachedPasswordChecker>,
+    tasks: Data<BackgroundTasks>,
+    settings: Data<Settings>,
 ) -> HttpResponse {
+    let locale = request_locale(&req);
+
+    let email = match normalize_email(&new_user.email, &settings.email_normalization) {
+        Ok(email) => email,
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::INFO, "Email rejected: {}", e);
+            return HttpResponse::BadRequest().json(ValidationErrorResponse {
+                base: ErrorResponse {
+                    error: t(locale, "error.invalid_request"),
+                },
+                fields: [(
+                    "email".to_string(),
+                    vec![FieldError {
+                        code: "email".to_string(),
+                        message: t(locale, "field.email"),
+                    }],
+                )]
+                .into_iter()
+                .collect(),
+            });
+        }
+    };
+
+    if let Some(next) = &new_user.next {
+        if !is_allowed_redirect(next, &settings) {
+            tracing::event!(target: "backend", tracing::Level::INFO, "Redirect target rejected: {}", next);
+            return HttpResponse::BadRequest().json(ValidationErrorResponse {
+                base: ErrorResponse {
+                    error: t(locale, "error.invalid_request"),
+                },
+                fields: [(
+                    "next".to_string(),
+                    vec![FieldError {
+                        code: "redirect_not_allowed".to_string(),
+                        message: t(locale, "field.redirect_not_allowed"),
+                    }],
+                )]
+                .into_iter()
+                .collect(),
+            });
+        }
+    }
+
+    let mut password_errors = validate_password(
+        &settings.password_policy,
+        &new_user.password,
+        &[&new_user.email, &new_user.first_name, &new_user.last_name],
+        locale,
+    )
+    .err()
+    .unwrap_or_default();
+
+    match is_password_breached(breached_passwords.into_inner(), new_user.password.clone()).await {
+        Ok(true) => password_errors.push(FieldError {
+            code: "password_breached".to_string(),
+            message: t(locale, "password.breached"),
+        }),
+        Ok(false) => {}
+        // Недоступность базы утечек не должна блокировать регистрацию.
+        Err(e) => {
+            tracing::event!(target: "backend", tracing::Level::ERROR, "Breached password check failed: {:#?}", e);
+        }
+    }
+
+    if !password_errors.is_empty() {
+        tracing::event!(target: "backend", tracing::Level::INFO, "Password rejected by policy: {} rule(s) failed", password_errors.len());
+        return HttpResponse::BadRequest().json(ValidationErrorResponse {
+            base: ErrorResponse {
+                error: t(locale, "error.password_requirements"),
+            },
+            fields: [("password".to_string(), password_errors)]
+                .into_iter()
+                .collect(),
+        });
+    }
+
     let mut transaction = match pool.begin().await {
         Ok(transaction) => transaction,
         Err(e) => {
             tracing::event!(target: "backend", tracing::Level::ERROR, "Unable to begin DB transaction: {:#?}", e);
             return HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "Something unexpected happened. Kindly try again.".to_string(),
+                error: t(locale, "error.unexpected"),
             });
         }
     };
 
-    let hashed_password = hash(new_user.0.password.as_bytes()).await;
+    let hashed_password = hash(new_user.0.password.as_bytes(), &settings.password_hashing).await;
+    let next = new_user.0.next;
 
     let create_new_user = CreateNewUser {
         password: hashed_password,
-        email: new_user.0.email,
+        email: email.address,
+        email_normalized: email.canonical,
         first_name: new_user.0.first_name,
         last_name: new_user.0.last_name,
+        locale,
     };
 
     let user_id = match insert_created_user_into_db(&mut transaction, &create_new_user).await {
         Ok(id) => id,
         Err(e) => {
             tracing::event!(target: "sqlx", tracing::Level::ERROR, "Failed to insert user into DB: {:#?}", e);
-            let error_message = if e
-                .as_database_error()
-                .unwrap()
-                .code()
-                .unwrap()
-                .parse::<i32>()
-                .unwrap()
-                == 23505
-            {
+            let email_taken = is_unique_violation(&e);
+            record_auth_event(
+                &pool,
+                &req,
+                NewAuthEvent::failure(AuthEventKind::Registration).reason(if email_taken {
+                    "email_taken"
+                } else {
+                    "insert_failed"
+                }),
+            )
+            .await;
+
+            if email_taken && settings.security.anti_enumeration {
+                // Отвечаем так же, как при успешной регистрации, а владельцу адреса пишем письмо.
+                notify_existing_account(
+                    &pool,
+                    &redis_pool,
+                    &tasks,
+                    &create_new_user.email_normalized,
+                    next.as_deref(),
+                    locale,
+                )
+                .await;
+                return registration_success_response(locale);
+            }
+
+            let error_message = if email_taken {
                 ErrorResponse {
-                    error: "A user with that email address already exists".to_string(),
+                    error: t(locale, "error.email_taken"),
                 }
             } else {
                 ErrorResponse {
-                    error: "Error inserting user into the database".to_string(),
+                    error: t(locale, "error.user_insert_failed"),
                 }
             };
             return HttpResponse::InternalServerError().json(error_message);
@@ -84,20 +230,25 @@ pub async fn register_user(
         .await
         .map_err(|e| {
             tracing::event!(target: "backend", tracing::Level::ERROR, "{}", e);
-            HttpResponse::InternalServerError().json(crate::types::ErrorResponse {
-                error: "We cannot activate your account at the moment".to_string(),
+            HttpResponse::InternalServerError().json(ErrorResponse {
+                error: t(locale, "error.activation_unavailable"),
             })
         })
         .expect("Redis connection cannot be gotten.");
 
-    send_multipart_email(
-        "RustAuth - Let's get you verified".to_string(),
+    let recipient = EmailRecipient {
         user_id,
-        create_new_user.email,
-        create_new_user.first_name,
-        create_new_user.last_name,
-        "verification_email.html",
+        email: create_new_user.email,
+        first_name: create_new_user.first_name,
+        last_name: create_new_user.last_name,
+        locale,
+    };
+    send_multipart_email(
+        recipient,
+        "verification_email",
+        next.as_deref(),
         &mut redis_con,
+        &tasks,
     )
     .await
     .unwrap();
@@ -107,13 +258,96 @@ pub async fn register_user(
     }
 
     tracing::event!(target: "backend", tracing::Level::INFO, "User created successfully");
-    HttpResponse::Ok().json(crate::types::SuccessResponse {
-        message: "Your account was created successfully. Check your email address to activate your \
-        account as we just sent you an activation link. Ensure you activate your account before the \
-        link expires".to_string(),
+    record_auth_event(
+        &pool,
+        &req,
+        NewAuthEvent::success(AuthEventKind::Registration).user(user_id),
+    )
+    .await;
+    registration_success_response(locale)
+}
+
+fn registration_success_response(locale: Locale) -> HttpResponse {
+    HttpResponse::Ok().json(SuccessResponse {
+        message: t(locale, "success.registered"),
     })
 }
 
+/// Код ошибки PostgreSQL `unique_violation`.
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|db_error| db_error.code())
+        .is_some_and(|code| code == "23505")
+}
+
+/// Письмо владельцу уже зарегистрированного адреса в режиме `anti_enumeration`.
+/// Письмо уходит на адрес из учётной записи, а не на введённый вариант написания.
+/// Если учётная запись ещё не активирована, повторно отправляем ссылку подтверждения,
+/// иначе сообщаем, что учётная запись уже существует.
+/// Письмо пишется на языке, сохранённом в учётной записи, а без него — на языке запроса.
+#[tracing::instrument(
+    name = "Notifying existing account owner",
+    skip(pool, redis_pool, tasks, email_normalized)
+)]
+async fn notify_existing_account(
+    pool: &PgPool,
+    redis_pool: &deadpool_redis::Pool,
+    tasks: &BackgroundTasks,
+    email_normalized: &str,
+    next: Option<&str>,
+    request_locale: Locale,
+) {
+    let existing_user = match sqlx::query(
+        "SELECT id, email, first_name, last_name, is_active, locale FROM users \
+        WHERE email_normalized = $1",
+    )
+    .bind(email_normalized)
+    .map(|row: sqlx::postgres::PgRow| {
+        let recipient = EmailRecipient {
+            user_id: row.get("id"),
+            email: row.get("email"),
+            first_name: row.get("first_name"),
+            last_name: row.get("last_name"),
+            locale: row
+                .get::<Option<String>, _>("locale")
+                .as_deref()
+                .and_then(Locale::from_tag)
+                .unwrap_or(request_locale),
+        };
+        (
+            recipient,
+            row.get::<Option<bool>, _>("is_active").unwrap_or(false),
+        )
+    })
+    .fetch_one(pool)
+    .await
+    {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::event!(target: "sqlx", tracing::Level::ERROR, "Existing user not found in DB: {:#?}", e);
+            return;
+        }
+    };
+    let (recipient, is_active) = existing_user;
+
+    let result = if is_active {
+        send_notification_email(recipient, "account_exists_email", tasks).await
+    } else {
+        match redis_pool.get().await {
+            Ok(mut redis_con) => {
+                send_multipart_email(recipient, "verification_email", next, &mut redis_con, tasks)
+                    .await
+                    .map(|_| ())
+            }
+            Err(e) => Err(format!("{}", e)),
+        }
+    };
+
+    if let Err(e) = result {
+        tracing::event!(target: "backend", tracing::Level::ERROR, "Could not notify existing account owner: {}", e);
+    }
+}
+
 #[tracing::instrument(name = "Inserting new user into DB",
 skip(transaction, new_user),
 fields(
@@ -126,13 +360,15 @@ async fn insert_created_user_into_db(
     new_user: &CreateNewUser,
 ) -> Result<uuid::Uuid, sqlx::Error> {
     let user_id = match sqlx::query(
-        "INSERT INTO users (email, password, first_name, last_name) \
-        VALUES ($1, $2, $3, $4) RETURNING id",
+        "INSERT INTO users (email, email_normalized, password, first_name, last_name, locale) \
+        VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
     )
     .bind(&new_user.email)
+    .bind(&new_user.email_normalized)
     .bind(&new_user.password)
     .bind(&new_user.first_name)
     .bind(&new_user.last_name)
+    .bind(new_user.locale.code())
     .map(|row: sqlx::postgres::PgRow| -> uuid::Uuid { row.get("id") })
     .fetch_one(&mut *transaction)
     .await
@@ -149,7 +385,7 @@ async fn insert_created_user_into_db(
         VALUES ($1) \
         ON CONFLICT (user_id) \
         DO NOTHING \
-        RETURN user_id",
+        RETURNING user_id",
     )
     .bind(user_id)
     .map(|row: sqlx::postgres::PgRow| -> uuid::Uuid { row.get("user_id") })
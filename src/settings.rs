@@ -1,5 +1,5 @@
 use config::{Config, File};
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
 use sqlx::postgres::PgConnectOptions;
 use sqlx::postgres::PgSslMode::{Prefer, Require};
 use sqlx::ConnectOptions;
@@ -14,7 +14,20 @@ pub struct Settings {
     pub redis: RedisSettings,
     pub secret: Secret,
     pub email: EmailSettings,
+    pub brand: BrandSettings,
     pub frontend_url: String,
+    pub cors: CorsSettings,
+    pub confirmation: ConfirmationSettings,
+    pub security: SecuritySettings,
+    pub password_policy: PasswordPolicy,
+    pub breached_passwords: BreachedPasswordSettings,
+    pub password_hashing: PasswordHashingSettings,
+    pub email_normalization: EmailNormalizationSettings,
+    pub metrics: MetricsSettings,
+    pub health: HealthSettings,
+    pub otlp: OtlpSettings,
+    pub session: SessionSettings,
+    pub security_headers: SecurityHeadersSettings,
 }
 
 /// Конкретные настройки приложения для предоставления доступа к `порту`,
@@ -26,6 +39,23 @@ pub struct ApplicationSettings {
     pub host: String,
     pub base_url: String,
     pub protocol: String,
+    /// Сколько секунд при остановке ждать завершения текущих запросов.
+    pub shutdown_timeout_seconds: u64,
+    /// Сколько секунд при остановке ждать фоновые задачи (отправку писем).
+    pub drain_timeout_seconds: u64,
+    pub tls: TlsSettings,
+}
+
+/// TLS на самом сервере приложения (rustls, HTTP/2 через ALPN).
+/// Сертификат и ключ в PEM перечитываются каждые `reload_interval_seconds`, если файлы изменились.
+/// `redirect_http_port` — порт дополнительного HTTP-слушателя, который перенаправляет на HTTPS.
+#[derive(Deserialize, Clone)]
+pub struct TlsSettings {
+    pub enabled: bool,
+    pub cert_path: String,
+    pub key_path: String,
+    pub reload_interval_seconds: u64,
+    pub redirect_http_port: Option<u16>,
 }
 
 /// Настройки базы данных для всего приложения
@@ -61,6 +91,242 @@ pub struct EmailSettings {
     pub host: String,
     pub host_user: String,
     pub host_user_password: String,
+    pub transport: EmailTransport,
+    /// Каталог шаблонов писем (`emails/*.html` и `emails/*.txt`).
+    pub templates_dir: String,
+    /// Перечитывать шаблоны с диска при каждом письме, чтобы правки были видны без перезапуска.
+    pub reload_templates: bool,
+}
+
+/// Оформление писем: название и ссылки, которые подставляются в общий макет и тему.
+/// `url` по умолчанию — `frontend_url`, `sender_name` — `name`.
+#[derive(Deserialize, Clone)]
+pub struct BrandSettings {
+    pub name: String,
+    pub sender_name: Option<String>,
+    pub url: Option<String>,
+    pub logo_url: Option<String>,
+    pub support_email: Option<String>,
+    pub accent_color: String,
+}
+
+impl BrandSettings {
+    /// Имя отправителя в заголовке `From`.
+    pub fn sender_name(&self) -> &str {
+        self.sender_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Настройки эндпоинта `/metrics` (Prometheus).
+/// Если задан `bind_address`, метрики отдаются отдельным сервером на этом адресе
+/// (например, `127.0.0.1:9100`), а не на основном порту приложения.
+/// Если задан `bearer_token`, без него метрики не отдаются. На основном порту
+/// он обязателен, см. `Settings::validate`.
+#[derive(Deserialize, Clone)]
+pub struct MetricsSettings {
+    pub enabled: bool,
+    pub bind_address: Option<String>,
+    pub bearer_token: Option<String>,
+}
+
+/// Экспорт трейсов в OpenTelemetry Collector по OTLP/gRPC, по умолчанию выключен.
+/// `sampling_ratio` — доля новых трейсов от 0 до 1; если запрос пришёл с `traceparent`,
+/// решение о сэмплировании берётся из него. `resource_attributes` добавляются к
+/// `service.name`, например `deployment.environment: staging`.
+#[derive(Deserialize, Clone)]
+pub struct OtlpSettings {
+    pub enabled: bool,
+    pub endpoint: String,
+    pub service_name: String,
+    pub sampling_ratio: f64,
+    pub resource_attributes: std::collections::HashMap<String, String>,
+}
+
+/// Настройки проверки готовности `/health/ready`.
+/// `timeout_ms` — предельное время каждой проверки,
+/// `check_smtp` — проверять ли подключение к SMTP-серверу.
+#[derive(Deserialize, Clone)]
+pub struct HealthSettings {
+    pub timeout_ms: u64,
+    pub check_smtp: bool,
+}
+
+/// Политика CORS. Origin из `frontend_url` разрешён всегда, к нему добавляются
+/// `allowed_origins` (например, админка) и шаблоны `allowed_origin_patterns` вида
+/// `https://*.preview.example.com`, где `*` — один или несколько поддоменов.
+/// Этот же список ограничивает адреса, на которые перенаправляет подтверждение регистрации.
+/// Списки можно задать и переменной окружения через запятую:
+/// `APP_CORS__ALLOWED_ORIGINS=https://admin.example.com,https://app.example.com`.
+#[derive(Deserialize, Clone)]
+pub struct CorsSettings {
+    #[serde(deserialize_with = "string_or_list")]
+    pub allowed_origins: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    pub allowed_origin_patterns: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    pub allowed_methods: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    pub allowed_headers: Vec<String>,
+    #[serde(deserialize_with = "string_or_list")]
+    pub expose_headers: Vec<String>,
+    pub max_age_seconds: Option<usize>,
+}
+
+/// Список из YAML или строка через запятую из переменной окружения.
+fn string_or_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrList {
+        List(Vec<String>),
+        String(String),
+    }
+
+    Ok(match StringOrList::deserialize(deserializer)? {
+        StringOrList::List(list) => list,
+        StringOrList::String(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+/// Куда перенаправлять пользователя после перехода по ссылке подтверждения.
+/// Путь, начинающийся с `/`, отсчитывается от `frontend_url`, иначе это полный адрес;
+/// в обоих случаях его origin должен быть разрешён настройками `cors`.
+/// К `invalid_token_url` и `error_url` добавляется `reason=<код ошибки>`.
+#[derive(Deserialize, Clone)]
+pub struct ConfirmationSettings {
+    pub success_url: String,
+    pub invalid_token_url: String,
+    pub error_url: String,
+}
+
+/// Атрибуты cookie сессии. Имя с префиксом `__Host-` браузер примет только
+/// с `secure`, `path: "/"` и без `domain`; это проверяется в `Settings::validate`.
+/// `max_age_seconds` не задан — cookie живёт до закрытия браузера.
+#[derive(Deserialize, Clone)]
+pub struct SessionSettings {
+    pub cookie_name: String,
+    pub cookie_domain: Option<String>,
+    pub cookie_path: String,
+    pub cookie_secure: bool,
+    pub cookie_http_only: bool,
+    pub cookie_same_site: CookieSameSite,
+    pub max_age_seconds: Option<i64>,
+}
+
+/// Значение атрибута `SameSite` cookie сессии.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CookieSameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<CookieSameSite> for actix_web::cookie::SameSite {
+    fn from(value: CookieSameSite) -> Self {
+        match value {
+            CookieSameSite::Strict => Self::Strict,
+            CookieSameSite::Lax => Self::Lax,
+            CookieSameSite::None => Self::None,
+        }
+    }
+}
+
+/// Заголовки безопасности, которые добавляются ко всем ответам.
+/// Незаданный (`~`) заголовок не отправляется; HSTS отправляется, только если
+/// `hsts_max_age_seconds` больше нуля. `X-Content-Type-Options: nosniff` отправляется всегда,
+/// когда `enabled`.
+#[derive(Deserialize, Clone)]
+pub struct SecurityHeadersSettings {
+    pub enabled: bool,
+    pub hsts_max_age_seconds: u64,
+    pub hsts_include_subdomains: bool,
+    pub hsts_preload: bool,
+    pub content_security_policy: Option<String>,
+    pub frame_options: Option<String>,
+    pub referrer_policy: Option<String>,
+    pub permissions_policy: Option<String>,
+}
+
+/// Способ доставки писем: `smtp` — настоящий SMTP-сервер,
+/// `memory` — письма складываются в память процесса (тесты, локальная разработка).
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransport {
+    Smtp,
+    Memory,
+}
+
+/// Настройки защиты учётных записей.
+/// `anti_enumeration` включает одинаковые ответы при входе и регистрации,
+/// чтобы по ним нельзя было узнать, существует ли учётная запись с данным email.
+#[derive(Deserialize, Clone)]
+pub struct SecuritySettings {
+    pub anti_enumeration: bool,
+    /// IP-адреса обратных прокси, которым разрешено передавать адрес клиента
+    /// в `Forwarded`/`X-Forwarded-For`. От остальных эти заголовки игнорируются.
+    #[serde(deserialize_with = "string_or_list")]
+    pub trusted_proxies: Vec<String>,
+}
+
+/// Требования к паролям при регистрации, сбросе и смене пароля.
+/// Длина считается в символах, `min_strength_score` — оценка zxcvbn от 0 до 4.
+#[derive(Deserialize, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub min_strength_score: u8,
+    pub reject_personal_info: bool,
+}
+
+/// Проверка паролей по локальной копии базы Pwned Passwords.
+/// `path` указывает на файл (`sorted`, `bloom`) или каталог (`range`).
+#[derive(Deserialize, Clone)]
+pub struct BreachedPasswordSettings {
+    pub enabled: bool,
+    pub format: BreachedPasswordFormat,
+    pub path: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum BreachedPasswordFormat {
+    Sorted,
+    Range,
+    Bloom,
+}
+
+/// Параметры хэширования паролей Argon2.
+/// `algorithm` — `argon2id`, `argon2i` или `argon2d`, `version` — 16 (0x10) или 19 (0x13),
+/// `m_cost` задаётся в КиБ. `pepper` — необязательный секрет, который подмешивается к каждому хэшу.
+#[derive(Deserialize, Clone)]
+pub struct PasswordHashingSettings {
+    pub algorithm: String,
+    pub version: u32,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+    pub pepper: Option<String>,
+}
+
+/// Правила, по которым адреса электронной почты считаются одной учётной записью.
+/// Регистр не учитывается всегда. `strip_plus_tags` отбрасывает `+метку` для любых доменов,
+/// `gmail_rules` — точки и `+метку` для `gmail.com`/`googlemail.com`.
+///
+/// Сохранённые ключи `email_normalized` сами не пересчитываются: после изменения правил
+/// обязательно выполните `backend-admin normalize-emails` (сначала с `--dry-run`).
+#[derive(Deserialize, Clone)]
+pub struct EmailNormalizationSettings {
+    pub strip_plus_tags: bool,
+    pub gmail_rules: bool,
 }
 
 impl DatabaseSettings {
@@ -81,6 +347,8 @@ impl DatabaseSettings {
 /// Среда выполнения для приложения.
 pub enum Environment {
     Development,
+    Testing,
+    Staging,
     Production,
 }
 
@@ -88,6 +356,8 @@ impl Environment {
     pub fn as_str(&self) -> &'static str {
         match self {
             Environment::Development => "development",
+            Environment::Testing => "testing",
+            Environment::Staging => "staging",
             Environment::Production => "production",
         }
     }
@@ -99,28 +369,241 @@ impl TryFrom<String> for Environment {
     fn try_from(s: String) -> Result<Self, Self::Error> {
         match s.to_lowercase().as_str() {
             "development" => Ok(Self::Development),
+            "testing" => Ok(Self::Testing),
+            "staging" => Ok(Self::Staging),
             "production" => Ok(Self::Production),
             other => Err(format!(
-                "{} is not a supported environment. Use either `development` or `production`.", //(не является поддерживаемой средой. Используйте либо "разработка", либо `производство`)
+                "{} is not a supported environment. \
+                Use `development`, `testing`, `staging` or `production`.",
                 other
             )),
         }
     }
 }
 
+impl Settings {
+    /// Проверяет настройки, которые иначе привели бы к панике или небезопасной работе
+    /// уже во время обработки запросов. Возвращает все найденные ошибки сразу.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+
+        // `SymmetricKey<V4>` принимает ровно 32 байта.
+        match self.secret.secret_key.len() {
+            0 => errors.push("secret.secret_key is empty".to_string()),
+            32 => {}
+            len => errors.push(format!(
+                "secret.secret_key must be exactly 32 bytes for SymmetricKey<V4>, got {}",
+                len
+            )),
+        }
+        // `Key::from` для cookie сессии требует не меньше 64 байт.
+        match self.secret.hmac_secret.len() {
+            0 => errors.push("secret.hmac_secret is empty".to_string()),
+            len if len < 64 => errors.push(format!(
+                "secret.hmac_secret must be at least 64 bytes, got {}",
+                len
+            )),
+            _ => {}
+        }
+        if self.secret.token_expiration <= 0 {
+            errors.push("secret.token_expiration must be a positive number of minutes".to_string());
+        }
+        if self.redis.pool_timeout_seconds == 0 {
+            errors.push("redis.pool_timeout_seconds must be greater than zero".to_string());
+        }
+        if self.redis.pool_expire_seconds == 0 {
+            errors.push("redis.pool_expire_seconds must be greater than zero".to_string());
+        }
+        if self.health.timeout_ms == 0 {
+            errors.push("health.timeout_ms must be greater than zero".to_string());
+        }
+        if self.application.tls.enabled {
+            if self.application.tls.cert_path.is_empty() || self.application.tls.key_path.is_empty()
+            {
+                errors.push(
+                    "application.tls.cert_path and key_path are required when TLS is enabled"
+                        .to_string(),
+                );
+            }
+            if self.application.tls.reload_interval_seconds == 0 {
+                errors.push(
+                    "application.tls.reload_interval_seconds must be greater than zero".to_string(),
+                );
+            }
+            if self.application.tls.redirect_http_port == Some(self.application.port) {
+                errors.push(
+                    "application.tls.redirect_http_port must differ from application.port"
+                        .to_string(),
+                );
+            }
+        }
+        for origin in &self.cors.allowed_origins {
+            if crate::utils::origin_of(origin).as_deref() != Some(origin.trim_end_matches('/')) {
+                errors.push(format!(
+                    "cors.allowed_origins: {} is not an origin like https://app.example.com",
+                    origin
+                ));
+            }
+        }
+        for pattern in &self.cors.allowed_origin_patterns {
+            if pattern.matches('*').count() != 1 || !pattern.contains("://*.") {
+                errors.push(format!(
+                    "cors.allowed_origin_patterns: {} must look like https://*.example.com",
+                    pattern
+                ));
+            }
+        }
+        if !self.frontend_url.is_empty() {
+            let confirmation = &self.confirmation;
+            for (name, target) in [
+                ("success_url", &confirmation.success_url),
+                ("invalid_token_url", &confirmation.invalid_token_url),
+                ("error_url", &confirmation.error_url),
+            ] {
+                let url = crate::utils::resolve_frontend_url(target, &self.frontend_url);
+                if !crate::utils::is_allowed_redirect(&url, self) {
+                    errors.push(format!(
+                        "confirmation.{}: {} is not allowed by the cors settings",
+                        name, url
+                    ));
+                }
+            }
+        }
+        let session = &self.session;
+        if session.cookie_name.is_empty() {
+            errors.push("session.cookie_name is empty".to_string());
+        }
+        if session.cookie_name.starts_with("__Host-")
+            && (!session.cookie_secure
+                || session.cookie_path != "/"
+                || session.cookie_domain.is_some())
+        {
+            errors.push(
+                "session.cookie_name with the __Host- prefix requires cookie_secure, \
+                cookie_path \"/\" and no cookie_domain"
+                    .to_string(),
+            );
+        }
+        if session.cookie_name.starts_with("__Secure-") && !session.cookie_secure {
+            errors.push(
+                "session.cookie_name with the __Secure- prefix requires cookie_secure".to_string(),
+            );
+        }
+        if session.cookie_same_site == CookieSameSite::None && !session.cookie_secure {
+            errors.push("session.cookie_same_site none requires cookie_secure".to_string());
+        }
+        if matches!(session.max_age_seconds, Some(max_age) if max_age <= 0) {
+            errors.push(
+                "session.max_age_seconds must be positive; remove it for a browser session"
+                    .to_string(),
+            );
+        }
+        if self.application.base_url.is_empty() {
+            errors.push("application.base_url is empty".to_string());
+        }
+        if self.frontend_url.is_empty() {
+            errors.push("frontend_url is empty".to_string());
+        }
+        if self.email.transport == EmailTransport::Smtp && self.email.host.is_empty() {
+            errors.push("email.host is empty while email.transport is smtp".to_string());
+        }
+        // Письма из памяти видны только в `/dev/mailbox`, который подключается при `debug`.
+        if self.email.transport == EmailTransport::Memory && !self.debug {
+            errors.push("email.transport memory requires debug: emails would be lost".to_string());
+        }
+        if self.password_policy.min_length > self.password_policy.max_length {
+            errors.push("password_policy.min_length is greater than max_length".to_string());
+        }
+        for proxy in &self.security.trusted_proxies {
+            if proxy.parse::<std::net::IpAddr>().is_err() {
+                errors.push(format!(
+                    "security.trusted_proxies: {} is not an IP address",
+                    proxy
+                ));
+            }
+        }
+        if matches!(&self.password_hashing.pepper, Some(pepper) if pepper.is_empty()) {
+            errors.push("password_hashing.pepper is empty; remove it or set a value".to_string());
+        }
+        if matches!(&self.metrics.bearer_token, Some(token) if token.is_empty()) {
+            errors.push("metrics.bearer_token is empty; remove it or set a value".to_string());
+        }
+        // Счётчики пулов и неудачных входов не должны быть видны всем на основном порту.
+        if self.metrics.enabled
+            && self.metrics.bind_address.is_none()
+            && self.metrics.bearer_token.is_none()
+        {
+            errors.push(
+                "metrics on the main listener require metrics.bearer_token; \
+                set it or move metrics to metrics.bind_address"
+                    .to_string(),
+            );
+        }
+        if self.otlp.enabled {
+            if self.otlp.endpoint.is_empty() {
+                errors.push("otlp.endpoint is empty while otlp is enabled".to_string());
+            }
+            if self.otlp.service_name.is_empty() {
+                errors.push("otlp.service_name is empty while otlp is enabled".to_string());
+            }
+        }
+        if !(0.0..=1.0).contains(&self.otlp.sampling_ratio) {
+            errors.push(format!(
+                "otlp.sampling_ratio must be between 0 and 1, got {}",
+                self.otlp.sampling_ratio
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Значения из файлов, заданных переменными `APP_<КЛЮЧ>_FILE`, как в Docker secrets:
+/// `APP_SECRET__HMAC_SECRET_FILE=/run/secrets/hmac_secret` задаёт `secret.hmac_secret`
+/// содержимым файла без завершающего перевода строки.
+fn secret_file_overrides() -> Result<Vec<(String, String)>, config::ConfigError> {
+    std::env::vars()
+        .filter_map(|(name, path)| {
+            let key = name.strip_prefix("APP_")?.strip_suffix("_FILE")?;
+            Some((key.to_lowercase().replace("__", "."), path))
+        })
+        .map(|(key, path)| {
+            std::fs::read_to_string(&path)
+                .map(|value| {
+                    (
+                        key.clone(),
+                        value.trim_end_matches(['\r', '\n']).to_string(),
+                    )
+                })
+                .map_err(|e| {
+                    config::ConfigError::Message(format!(
+                        "Failed to read {} from {}: {}",
+                        key, path, e
+                    ))
+                })
+        })
+        .collect()
+}
+
 /// Многоцелевая функция, которая помогает определить текущую среду приложения
 /// выполняется с использованием переменной окружения `APP_ENVIRONMENT`.
 ///
 /// \`\`\`
-/// APP_ENVIRONMENT = development | production.
+/// APP_ENVIRONMENT = development | testing | staging | production.
 /// \`\`\`
 ///
-/// После обнаружения он загружает соответствующий файл .yaml
-/// затем он загружает переменную окружения, которая переопределяет все, что задано в файле .yaml.
+/// Сначала загружается `base.yaml`, поверх него — файл среды, например `production.yaml`,
+/// затем переменные окружения, которые переопределяют все, что задано в файлах .yaml.
 /// Чтобы это сработало, переменная окружения ДОЛЖНА быть в верхнем регистре и начинаться с `APP`,
 /// разделитель `_`, затем категория настроек,
 /// за которым следует разделитель `__` а затем переменная
-/// например `APP_APPLICATION__PORT=5001` для порта, который должен быть установлен как `5001`
+/// например `APP_APPLICATION__PORT=5001` для порта, который должен быть установлен как `5001`.
+/// Последними применяются файлы секретов `APP_<КЛЮЧ>_FILE`, см. `secret_file_overrides`.
+/// Итоговые настройки проверяются `Settings::validate`.
 pub fn get_settings() -> Result<Settings, config::ConfigError> {
     let base_path = current_dir().expect(
         "Failed to determine the current directory (Не удалось определить текущий каталог)",
@@ -135,9 +618,13 @@ pub fn get_settings() -> Result<Settings, config::ConfigError> {
         .expect("Failed to parse APP_ENVIRONMENT (Не удалось проанализировать APP_ENVIRONMENT).");
     let environment_filename = format!("{}.yaml", environment.as_str());
 
-    println!("получаем настройки из файла {}", environment_filename);
+    println!(
+        "получаем настройки из файлов base.yaml и {}",
+        environment_filename
+    );
 
-    let settings = Config::builder()
+    let mut builder = Config::builder()
+        .add_source(File::from(setting_directory.join("base.yaml")))
         .add_source(File::from(setting_directory.join(environment_filename)))
         // Добавить настройки из переменных окружения (с префиксом APP и '__' в качестве разделителя)
         // Например. `APP_APPLICATION__PORT=5001 установит `Settings.application.port`
@@ -145,8 +632,15 @@ pub fn get_settings() -> Result<Settings, config::ConfigError> {
             config::Environment::with_prefix("APP")
                 .prefix_separator("_")
                 .separator("__"),
-        )
-        .build()?;
+        );
+    for (key, value) in secret_file_overrides()? {
+        builder = builder.set_override(key, value)?;
+    }
+
+    let settings = builder.build()?.try_deserialize::<Settings>()?;
+    settings.validate().map_err(|errors| {
+        config::ConfigError::Message(format!("Invalid settings:\n  - {}", errors.join("\n  - ")))
+    })?;
 
-    settings.try_deserialize::<Settings>()
+    Ok(settings)
 }
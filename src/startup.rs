@@ -1,22 +1,62 @@
-use crate::routes::{auth_routes_config, health_check};
-use crate::settings::{DatabaseSettings, Settings};
+use crate::middleware::{
+    CsrfProtection, RequestMetrics, RequestTracing, SecurityHeaders, REQUEST_ID_HEADER,
+};
+use crate::routes::{
+    api_doc, auth_routes_config, dev_routes_config, health_check, health_live, health_ready,
+    openapi_json, prometheus_metrics,
+};
+use crate::settings::{DatabaseSettings, RedisSettings, SessionSettings, Settings};
+use crate::tls::{
+    redirect_to_https, server_config, spawn_certificate_reloader, ReloadingCertResolver,
+};
+use crate::utils::{
+    is_allowed_origin, BackgroundTasks, BreachedPasswordChecker, TrustedProxies, CSRF_HEADER,
+};
+use actix_cors::Cors;
+use actix_session::config::{BrowserSession, PersistentSession};
 use actix_session::storage::CookieSessionStore;
 use actix_session::SessionMiddleware;
-use actix_web::cookie::{Key, SameSite};
+use actix_web::cookie::{time, Key};
 use actix_web::dev::Server;
-use actix_web::web::Data;
+use actix_web::web::{self, Data, ServiceConfig};
 use actix_web::{App, HttpServer};
+use sqlx::migrate::Migrator;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use std::io::Error;
 use std::net::TcpListener;
+use std::sync::Arc;
 use std::time::Duration;
-use actix_cors::Cors;
-use actix_web::http::header::{ACCEPT, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_TYPE};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use utoipa_redoc::{Redoc, Servable};
+
+/// Миграции, встроенные в бинарник. Их же сверяет с БД проверка готовности.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 pub struct Application {
     port: u16,
     server: Server,
+    /// Вспомогательные серверы: метрики и редирект с HTTP на HTTPS.
+    auxiliary_servers: Vec<Server>,
+    certificate_reloader: Option<JoinHandle<()>>,
+    db_pool: PgPool,
+    redis_pool: deadpool_redis::Pool,
+    shutdown: CancellationToken,
+    background_tasks: BackgroundTasks,
+    drain_timeout: Duration,
+}
+
+/// Позволяет остановить приложение так же, как по SIGTERM: из встраивающего кода или теста.
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    token: CancellationToken,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.token.cancel();
+    }
 }
 
 impl Application {
@@ -27,7 +67,7 @@ impl Application {
             get_connection_pool(&settings.database).await
         };
 
-        sqlx::migrate!("../backend/migrations")
+        MIGRATOR
             .run(&connection_pool)
             .await
             .expect("Failed to migrate the database (Не удалось перенести базу данных).");
@@ -37,19 +77,142 @@ impl Application {
             settings.application.host, settings.application.port
         );
 
+        let redis_pool = get_redis_pool(&settings.redis);
+
+        let mut auxiliary_servers = Vec::new();
+
+        // Метрики на отдельном адресе, недоступном снаружи, если он задан
+        if let Some(metrics_address) = &settings.metrics.bind_address {
+            if settings.metrics.enabled {
+                auxiliary_servers.push(run_metrics(
+                    TcpListener::bind(metrics_address)?,
+                    connection_pool.clone(),
+                    redis_pool.clone(),
+                    settings.clone(),
+                )?);
+            }
+        }
+
+        let tls = &settings.application.tls;
+        let (tls_config, certificate_reloader) = if tls.enabled {
+            let resolver = Arc::new(
+                ReloadingCertResolver::new(tls)
+                    .map_err(|e| Error::new(std::io::ErrorKind::InvalidInput, e))?,
+            );
+            let reloader = spawn_certificate_reloader(
+                resolver.clone(),
+                Duration::from_secs(tls.reload_interval_seconds),
+            );
+            (Some(server_config(resolver)), Some(reloader))
+        } else {
+            (None, None)
+        };
+
+        let drain_timeout = Duration::from_secs(settings.application.drain_timeout_seconds);
+
         let listener = TcpListener::bind(&address)?;
         let port = listener.local_addr().unwrap().port();
-        let server = run(listener, connection_pool, settings).await?;
 
-        Ok(Self { port, server })
+        if let Some(redirect_port) = tls.redirect_http_port.filter(|_| tls.enabled) {
+            auxiliary_servers.push(run_https_redirect(
+                TcpListener::bind(format!("{}:{}", settings.application.host, redirect_port))?,
+                port,
+            )?);
+        }
+
+        let background_tasks = BackgroundTasks::default();
+
+        let server = run(
+            listener,
+            connection_pool.clone(),
+            redis_pool.clone(),
+            background_tasks.clone(),
+            settings,
+            tls_config,
+        )
+        .await?;
+
+        Ok(Self {
+            port,
+            server,
+            auxiliary_servers,
+            certificate_reloader,
+            db_pool: connection_pool,
+            redis_pool,
+            shutdown: CancellationToken::new(),
+            background_tasks,
+            drain_timeout,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle {
+            token: self.shutdown.clone(),
+        }
+    }
+
+    /// Работает до SIGINT/SIGTERM или `ShutdownHandle::shutdown`, затем останавливается по шагам:
+    /// перестаёт принимать соединения и ждёт текущие запросы (`shutdown_timeout_seconds`),
+    /// дожидается фоновых задач, например отправки писем (`drain_timeout_seconds`),
+    /// и закрывает пулы Postgres и Redis.
     pub async fn run_until_stopped(self) -> Result<(), Error> {
-        self.server.await
+        let server_handle = self.server.handle();
+        // Сервер работает в отдельной задаче: `stop` лишь просит его остановиться,
+        // а завершения ждём через `JoinHandle`
+        let mut server_task = tokio::spawn(self.server);
+        let auxiliary_handles: Vec<_> = self
+            .auxiliary_servers
+            .into_iter()
+            .map(|server| {
+                let handle = server.handle();
+                tokio::spawn(server);
+                handle
+            })
+            .collect();
+
+        tokio::select! {
+            result = &mut server_task => result.map_err(Error::other)??,
+            _ = shutdown_signal(self.shutdown) => {
+                tracing::event!(target: "backend", tracing::Level::INFO, "Shutting down: waiting for in-flight requests");
+                server_handle.stop(true).await;
+                server_task.await.map_err(Error::other)??;
+            }
+        }
+        for handle in auxiliary_handles {
+            handle.stop(true).await;
+        }
+        if let Some(certificate_reloader) = self.certificate_reloader {
+            certificate_reloader.abort();
+        }
+
+        self.background_tasks.drain(self.drain_timeout).await;
+
+        self.db_pool.close().await;
+        self.redis_pool.close();
+        tracing::event!(target: "backend", tracing::Level::INFO, "Shutdown complete");
+        Ok(())
+    }
+}
+
+async fn shutdown_signal(token: CancellationToken) {
+    let terminate = async {
+        #[cfg(unix)]
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler.")
+            .recv()
+            .await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate => {}
+        _ = token.cancelled() => {}
     }
 }
 
@@ -59,47 +222,187 @@ pub async fn get_connection_pool(settings: &DatabaseSettings) -> PgPool {
         .connect_lazy_with(settings.connect_to_db())
 }
 
-async fn run(listener: TcpListener, db_pool: PgPool, settings: Settings) -> Result<Server, Error> {
+pub fn get_redis_pool(settings: &RedisSettings) -> deadpool_redis::Pool {
+    deadpool_redis::Config::from_url(settings.uri.clone())
+        .create_pool(Some(deadpool_redis::Runtime::Tokio1))
+        .expect("Cannot create deadpool redis (Не удается создать deadpool_redis.).")
+}
+
+/// Эндпоинт `/metrics` на основном сервере: только если метрики включены
+/// и для них не задан отдельный адрес.
+fn metrics_config(cfg: &mut ServiceConfig, settings: &Settings) {
+    if settings.metrics.enabled && settings.metrics.bind_address.is_none() {
+        cfg.service(prometheus_metrics);
+    }
+}
+
+/// Отдельный сервер, который отдаёт только `/metrics`.
+fn run_metrics(
+    listener: TcpListener,
+    db_pool: PgPool,
+    redis_pool: deadpool_redis::Pool,
+    settings: Settings,
+) -> Result<Server, Error> {
+    let pool = Data::new(db_pool);
+    let redis_pool_data = Data::new(redis_pool);
+    let settings_data = Data::new(settings);
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .service(prometheus_metrics)
+            .app_data(pool.clone())
+            .app_data(redis_pool_data.clone())
+            .app_data(settings_data.clone())
+    })
+    .workers(1)
+    .disable_signals()
+    .listen(listener)?
+    .run();
+
+    Ok(server)
+}
+
+/// HTTP-слушатель, который отвечает на всё редиректом 308 на тот же путь по HTTPS.
+fn run_https_redirect(listener: TcpListener, https_port: u16) -> Result<Server, Error> {
+    let server = HttpServer::new(move || {
+        App::new().default_service(web::to(move |req| redirect_to_https(req, https_port)))
+    })
+    .workers(1)
+    .disable_signals()
+    .listen(listener)?
+    .run();
+
+    Ok(server)
+}
+
+/// CORS по настройкам `cors`. Cookie сессии передаётся между origin, поэтому
+/// `supports_credentials`; заголовки CSRF-токена и идентификатора запроса разрешены всегда.
+fn cors_middleware(settings: &Settings) -> Cors {
+    let cors = &settings.cors;
+    let origin_settings = settings.clone();
+
+    Cors::default()
+        .allowed_origin_fn(move |origin, _request_head| {
+            origin
+                .to_str()
+                .is_ok_and(|origin| is_allowed_origin(origin, &origin_settings))
+        })
+        .allowed_methods(cors.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(cors.allowed_headers.iter().map(String::as_str))
+        .allowed_header(CSRF_HEADER)
+        .allowed_header(REQUEST_ID_HEADER)
+        .expose_headers(cors.expose_headers.iter().map(String::as_str))
+        .expose_headers([REQUEST_ID_HEADER])
+        .supports_credentials()
+        .max_age(cors.max_age_seconds)
+}
+
+/// Cookie сессии со всеми атрибутами из настроек `session`.
+fn session_middleware(
+    settings: &SessionSettings,
+    secret_key: Key,
+) -> SessionMiddleware<CookieSessionStore> {
+    let builder = SessionMiddleware::builder(CookieSessionStore::default(), secret_key)
+        .cookie_name(settings.cookie_name.clone())
+        .cookie_domain(settings.cookie_domain.clone())
+        .cookie_path(settings.cookie_path.clone())
+        .cookie_secure(settings.cookie_secure)
+        .cookie_http_only(settings.cookie_http_only)
+        .cookie_same_site(settings.cookie_same_site.into());
+
+    match settings.max_age_seconds {
+        Some(max_age) => builder.session_lifecycle(
+            PersistentSession::default().session_ttl(time::Duration::seconds(max_age)),
+        ),
+        None => builder.session_lifecycle(BrowserSession::default()),
+    }
+    .build()
+}
+
+/// Интерактивная документация API (Redoc) по адресу `/docs`, только в режиме отладки.
+fn api_docs_ui_config(cfg: &mut ServiceConfig, debug: bool) {
+    if debug {
+        cfg.service(Redoc::with_url("/docs", api_doc()));
+    }
+}
+
+async fn run(
+    listener: TcpListener,
+    db_pool: PgPool,
+    redis_pool: deadpool_redis::Pool,
+    background_tasks: BackgroundTasks,
+    settings: Settings,
+    tls_config: Option<rustls::ServerConfig>,
+) -> Result<Server, Error> {
     // Состояние приложения пула подключений к базе данных
     let pool = Data::new(db_pool);
 
     // Пул подключений Redis
-    let cfg = deadpool_redis::Config::from_url(settings.clone().redis.uri);
-    let redis_pool = cfg
-        .create_pool(Some(deadpool_redis::Runtime::Tokio1))
-        .expect("Cannot create deadpool redis (Не удается создать deadpool_redis.).");
     let redis_pool_data = Data::new(redis_pool);
 
+    // Локальная база утёкших паролей
+    let breached_passwords = BreachedPasswordChecker::from_settings(&settings.breached_passwords)
+        .expect(
+            "Cannot load breached passwords corpus (Не удается загрузить базу утёкших паролей).",
+        );
+    let breached_passwords_data = Data::new(breached_passwords);
+
+    // Прокси, которым доверяем адрес клиента
+    let trusted_proxies = TrustedProxies::from_settings(&settings.security)
+        .map_err(|e| Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let trusted_proxies_data = Data::new(trusted_proxies);
+
+    // Фоновые задачи, которых `Application` дожидается при остановке
+    let background_tasks_data = Data::new(background_tasks);
+
+    let shutdown_timeout = settings.application.shutdown_timeout_seconds;
+
+    // Настройки, прочитанные один раз при запуске
+    let settings_data = Data::new(settings.clone());
+
     //Создание сессии
     let secret_key = Key::from(settings.secret.hmac_secret.as_bytes());
 
+    let security_headers = SecurityHeaders::new(&settings.security_headers)
+        .map_err(|e| Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
     let server = HttpServer::new(move || {
         App::new()
-            .wrap(if settings.debug {
-                SessionMiddleware::builder(CookieSessionStore::default(), secret_key.clone())
-                    .cookie_http_only(true)
-                    .cookie_same_site(SameSite::None)
-                    .cookie_secure(true)
-                    .build()
-            } else {
-                SessionMiddleware::new(CookieSessionStore::default(), secret_key.clone())
-            })
-            .wrap(Cors::default()
-                      .allowed_origin(&settings.frontend_url)
-                      .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
-                      .allowed_headers(vec![AUTHORIZATION, ACCEPT])
-                      .allowed_header(CONTENT_TYPE)
-                      .expose_headers(&CONTENT_DISPOSITION)
-                      .supports_credentials()
-                      .max_age(3600),
-            )
+            // Внутри `SessionMiddleware`: проверке нужна уже загруженная сессия
+            .wrap(CsrfProtection)
+            .wrap(session_middleware(&settings.session, secret_key.clone()))
+            .wrap(cors_middleware(&settings))
+            // Заголовки добавляются и к ответам CORS на preflight
+            .wrap(security_headers.clone())
+            // В замер попадает и обработка сессии и CORS
+            .wrap(RequestMetrics)
+            // Последний `wrap` — внешний: span запроса охватывает все остальные middleware
+            .wrap(RequestTracing)
             .service(health_check)
+            .service(health_live)
+            .service(health_ready)
+            .service(openapi_json)
+            .configure(|cfg| metrics_config(cfg, &settings))
+            .configure(|cfg| api_docs_ui_config(cfg, settings.debug))
+            .configure(|cfg| dev_routes_config(cfg, settings.debug))
             .configure(auth_routes_config) //Маршруты  аутентификации
             //Добавляем, в состояние приложения, пул баз данных и пул Redis
             .app_data(pool.clone())
             .app_data(redis_pool_data.clone())
+            .app_data(breached_passwords_data.clone())
+            .app_data(trusted_proxies_data.clone())
+            .app_data(background_tasks_data.clone())
+            .app_data(settings_data.clone())
     })
-    .listen(listener)?
+    // Сигналы обрабатывает `Application::run_until_stopped`
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout);
+
+    // С TLS actix сам объявляет через ALPN `h2` и `http/1.1`
+    let server = match tls_config {
+        Some(tls_config) => server.listen_rustls(listener, tls_config)?,
+        None => server.listen(listener)?,
+    }
     .run();
 
     Ok(server)
@@ -1,7 +1,18 @@
+use crate::settings::OtlpSettings;
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, Tracer};
+use opentelemetry_sdk::Resource;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{fmt, EnvFilter, Registry};
 
-pub fn get_subscriber(debug: bool) -> impl tracing::Subscriber + Send + Sync {
+/// Подписчик с логами в stdout и, если передан `otlp_tracer`, экспортом span в OTLP.
+pub fn get_subscriber(
+    debug: bool,
+    otlp_tracer: Option<Tracer>,
+) -> impl tracing::Subscriber + Send + Sync {
     let env_filter = if debug {
         "trace".to_string()
     } else {
@@ -10,8 +21,13 @@ pub fn get_subscriber(debug: bool) -> impl tracing::Subscriber + Send + Sync {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
 
+    let otlp_layer = otlp_tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+
     let stdout_log = fmt::layer().pretty();
-    let subscriber = Registry::default().with(env_filter).with(stdout_log);
+    let subscriber = Registry::default()
+        .with(env_filter)
+        .with(otlp_layer)
+        .with(stdout_log);
 
     let json_log = if !debug {
         let json_log = fmt::layer().json();
@@ -20,12 +36,55 @@ pub fn get_subscriber(debug: bool) -> impl tracing::Subscriber + Send + Sync {
         None
     };
 
-    let subscriber = subscriber.with(json_log);
-
-    subscriber
+    subscriber.with(json_log)
 }
 
 pub fn init_subscriber(subscriber: impl tracing::Subscriber + Send + Sync) {
     tracing::subscriber::set_global_default(subscriber)
         .expect("Filed to set subscriber (Не удалось установить подписчика)")
 }
+
+/// Запускает пакетный экспорт трейсов в коллектор по OTLP/gRPC, если он включён в настройках.
+/// Вызывается внутри рантайма tokio: экспорт работает фоновой задачей.
+/// Заодно включает W3C Trace Context, чтобы `RequestTracing` продолжал трейс из `traceparent`.
+pub fn otlp_tracer(settings: &OtlpSettings) -> Result<Option<Tracer>, TraceError> {
+    if !settings.enabled {
+        return Ok(None);
+    }
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let attributes = settings
+        .resource_attributes
+        .iter()
+        .map(|(key, value)| KeyValue::new(key.clone(), value.clone()))
+        .chain([KeyValue::new("service.name", settings.service_name.clone())]);
+    // Запрос с `traceparent` следует решению вызывающего сервиса, новые трейсы — доле `sampling_ratio`.
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+        settings.sampling_ratio,
+    )));
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&settings.endpoint),
+        )
+        .with_trace_config(
+            opentelemetry_sdk::trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::default().merge(&Resource::new(attributes))),
+        )
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+
+    Ok(Some(tracer))
+}
+
+/// Отправляет накопленные span в коллектор перед выходом.
+pub async fn shutdown_otlp_tracer() {
+    // `shutdown_tracer_provider` блокирует поток, пока пакетная задача не выгрузит очередь.
+    tokio::task::spawn_blocking(opentelemetry::global::shutdown_tracer_provider)
+        .await
+        .ok();
+}
@@ -0,0 +1,176 @@
+//! TLS на rustls: сертификат и ключ читаются из файлов, указанных в настройках `tls`,
+//! и перечитываются при их изменении без перезапуска сервера.
+use crate::settings::TlsSettings;
+use actix_web::http::header::LOCATION;
+use actix_web::{HttpRequest, HttpResponse};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::{any_supported_type, CertifiedKey};
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+
+/// Отдаёт текущую пару сертификат/ключ для каждого TLS-рукопожатия.
+/// `reload_if_changed` заменяет её, если файлы изменились; уже открытые соединения
+/// продолжают работать со старым сертификатом.
+pub struct ReloadingCertResolver {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    certified_key: RwLock<Arc<CertifiedKey>>,
+    loaded_modified: Mutex<Option<SystemTime>>,
+}
+
+impl ReloadingCertResolver {
+    pub fn new(settings: &TlsSettings) -> Result<Self, String> {
+        let cert_path = PathBuf::from(&settings.cert_path);
+        let key_path = PathBuf::from(&settings.key_path);
+        let loaded_modified = last_modified(&cert_path, &key_path);
+        let certified_key = load_certified_key(&cert_path, &key_path)?;
+
+        Ok(Self {
+            cert_path,
+            key_path,
+            certified_key: RwLock::new(Arc::new(certified_key)),
+            loaded_modified: Mutex::new(loaded_modified),
+        })
+    }
+
+    /// Перечитывает сертификат, если время изменения файлов другое.
+    /// При ошибке продолжает использоваться прежний сертификат.
+    pub fn reload_if_changed(&self) -> Result<bool, String> {
+        let modified = last_modified(&self.cert_path, &self.key_path);
+        let mut loaded_modified = self
+            .loaded_modified
+            .lock()
+            .expect("TLS resolver lock is poisoned.");
+        if modified == *loaded_modified {
+            return Ok(false);
+        }
+
+        let certified_key = load_certified_key(&self.cert_path, &self.key_path)?;
+        *self
+            .certified_key
+            .write()
+            .expect("TLS resolver lock is poisoned.") = Arc::new(certified_key);
+        *loaded_modified = modified;
+        Ok(true)
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(
+            self.certified_key
+                .read()
+                .expect("TLS resolver lock is poisoned.")
+                .clone(),
+        )
+    }
+}
+
+/// Конфигурация rustls с безопасными настройками по умолчанию.
+/// ALPN (`h2`, `http/1.1`) добавляет сам actix в `listen_rustls`.
+pub fn server_config(resolver: Arc<ReloadingCertResolver>) -> ServerConfig {
+    ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver)
+}
+
+/// Раз в `interval` проверяет файлы сертификата и ключа и перечитывает их при изменении.
+pub fn spawn_certificate_reloader(
+    resolver: Arc<ReloadingCertResolver>,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match resolver.reload_if_changed() {
+                Ok(true) => {
+                    tracing::event!(target: "backend", tracing::Level::INFO, "TLS certificate reloaded");
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::event!(target: "backend", tracing::Level::ERROR, "Failed to reload TLS certificate, keeping the previous one: {}", e);
+                }
+            }
+        }
+    })
+}
+
+/// Обработчик для HTTP-слушателя: постоянный редирект на тот же путь по HTTPS.
+pub async fn redirect_to_https(req: HttpRequest, https_port: u16) -> HttpResponse {
+    let connection_info = req.connection_info();
+    let host = strip_port(connection_info.host());
+    let authority = if https_port == 443 {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, https_port)
+    };
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or("/");
+
+    HttpResponse::PermanentRedirect()
+        .insert_header((LOCATION, format!("https://{}{}", authority, path)))
+        .finish()
+}
+
+fn strip_port(host: &str) -> &str {
+    match host.rsplit_once(':') {
+        // `[::1]` без порта: двоеточия внутри IPv6-адреса
+        Some((name, port)) if !port.contains(']') && port.chars().all(|c| c.is_ascii_digit()) => {
+            name
+        }
+        _ => host,
+    }
+}
+
+fn last_modified(cert_path: &Path, key_path: &Path) -> Option<SystemTime> {
+    let modified = |path: &Path| {
+        path.metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+    modified(cert_path).max(modified(key_path))
+}
+
+fn load_certified_key(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, String> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| format!("Cannot open {}: {}", path.display(), e))
+    };
+
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut open(cert_path)?)
+        .map_err(|e| format!("Cannot parse {}: {}", cert_path.display(), e))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", cert_path.display()));
+    }
+
+    let mut key_reader = open(key_path)?;
+    let key = loop {
+        match rustls_pemfile::read_one(&mut key_reader)
+            .map_err(|e| format!("Cannot parse {}: {}", key_path.display(), e))?
+        {
+            Some(rustls_pemfile::Item::RSAKey(key))
+            | Some(rustls_pemfile::Item::PKCS8Key(key))
+            | Some(rustls_pemfile::Item::ECKey(key)) => break PrivateKey(key),
+            Some(_) => continue,
+            None => return Err(format!("No private key found in {}", key_path.display())),
+        }
+    };
+    let signing_key = any_supported_type(&key)
+        .map_err(|e| format!("Unsupported private key in {}: {}", key_path.display(), e))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
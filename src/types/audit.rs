@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Тип события аутентификации, сохраняемого в журнале `auth_events`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventKind {
+    Login,
+    Logout,
+    Registration,
+    Activation,
+    PasswordReset,
+    PasswordChange,
+    MfaChange,
+    AdminAction,
+}
+
+impl AuthEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthEventKind::Login => "login",
+            AuthEventKind::Logout => "logout",
+            AuthEventKind::Registration => "registration",
+            AuthEventKind::Activation => "activation",
+            AuthEventKind::PasswordReset => "password_reset",
+            AuthEventKind::PasswordChange => "password_change",
+            AuthEventKind::MfaChange => "mfa_change",
+            AuthEventKind::AdminAction => "admin_action",
+        }
+    }
+}
+
+/// Результат события аутентификации.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthEventOutcome {
+    Success,
+    Failure,
+}
+
+impl AuthEventOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthEventOutcome::Success => "success",
+            AuthEventOutcome::Failure => "failure",
+        }
+    }
+}
+
+/// Событие, которое ещё не записано в БД.
+/// Собирается цепочкой вызовов, например
+/// `NewAuthEvent::failure(AuthEventKind::Login).reason("wrong_password")`.
+#[derive(Debug, Clone)]
+pub struct NewAuthEvent {
+    pub kind: AuthEventKind,
+    pub outcome: AuthEventOutcome,
+    pub user_id: Option<Uuid>,
+    pub details: Option<serde_json::Value>,
+}
+
+impl NewAuthEvent {
+    pub fn success(kind: AuthEventKind) -> Self {
+        Self {
+            kind,
+            outcome: AuthEventOutcome::Success,
+            user_id: None,
+            details: None,
+        }
+    }
+
+    pub fn failure(kind: AuthEventKind) -> Self {
+        Self {
+            kind,
+            outcome: AuthEventOutcome::Failure,
+            user_id: None,
+            details: None,
+        }
+    }
+
+    pub fn user(mut self, user_id: Uuid) -> Self {
+        self.user_id = Some(user_id);
+        self
+    }
+
+    /// Короткая машиночитаемая причина, сохраняется в `details.reason`.
+    pub fn reason(self, reason: &str) -> Self {
+        self.details(serde_json::json!({ "reason": reason }))
+    }
+
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Запись журнала событий аутентификации в том виде, в котором она отдаётся клиенту.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AuthEvent {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub event_type: String,
+    pub outcome: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Страница журнала событий.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AuthEventPage {
+    pub events: Vec<AuthEvent>,
+    pub page: i64,
+    pub page_size: i64,
+    pub total: i64,
+}
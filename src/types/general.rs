@@ -1,16 +1,50 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+/// Ответ с ошибкой. `RequestTracing` добавляет в него поле `request_id`.
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
 }
 
-#[derive(Serialize, Deserialize)]
+/// `ErrorResponse` с машиночитаемым кодом для клиентов, которые не следуют редиректам.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CodedErrorResponse {
+    #[serde(flatten)]
+    pub base: ErrorResponse,
+    pub code: String,
+}
+
+/// Ошибка конкретного поля запроса: машиночитаемый код и сообщение для пользователя.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct FieldError {
+    pub code: String,
+    pub message: String,
+}
+
+/// `ErrorResponse`, дополненный списком нарушенных правил по каждому полю.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ValidationErrorResponse {
+    #[serde(flatten)]
+    pub base: ErrorResponse,
+    pub fields: std::collections::BTreeMap<String, Vec<FieldError>>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct SuccessResponse {
     pub message: String,
 }
 
+/// CSRF-токен сессии: его нужно передавать в заголовке `X-CSRF-Token`
+/// в запросах POST, PUT, PATCH и DELETE.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CsrfTokenResponse {
+    pub csrf_token: String,
+}
+
 pub const USER_ID_KEY: &str = "user_id";
 pub const USER_EMAIL_KEY: &str = "user_email";
 pub const USER_IS_STAFF_KEY: &str = "user_is_staff";
 pub const USER_IS_SUPERUSER: &str = "user_is_superuser";
+pub const CSRF_TOKEN_KEY: &str = "csrf_token";
+pub const USER_LOCALE_KEY: &str = "user_locale";
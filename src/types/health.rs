@@ -0,0 +1,28 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+/// Состояние отдельной зависимости или приложения в целом.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Error,
+    Skipped,
+}
+
+/// Результат проверки одной зависимости.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DependencyCheck {
+    pub status: HealthStatus,
+    pub duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Ответ `/health/ready`: общий статус и проверки по каждой зависимости.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ReadinessReport {
+    pub status: HealthStatus,
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
@@ -1,12 +1,19 @@
+mod audit;
 mod general;
+mod health;
 mod token;
 mod users;
 
+pub use audit::{AuthEvent, AuthEventKind, AuthEventOutcome, AuthEventPage, NewAuthEvent};
+
+pub use health::{DependencyCheck, HealthStatus, ReadinessReport};
+
 pub use token::ConfirmationToken;
 
 pub use general::{
-    ErrorResponse, SuccessResponse, USER_EMAIL_KEY, USER_ID_KEY, USER_IS_STAFF_KEY,
-    USER_IS_SUPERUSER,
+    CodedErrorResponse, CsrfTokenResponse, ErrorResponse, FieldError, SuccessResponse,
+    ValidationErrorResponse, CSRF_TOKEN_KEY, USER_EMAIL_KEY, USER_ID_KEY, USER_IS_STAFF_KEY,
+    USER_IS_SUPERUSER, USER_LOCALE_KEY,
 };
 
-pub use users::{LoggedInUser, User, UserVisible};
+pub use users::{ConfirmationResponse, LoggedInUser, User, UserVisible};
@@ -3,4 +3,6 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ConfirmationToken {
     pub user_id: uuid::Uuid,
+    /// Куда перенаправить пользователя после подтверждения, если задано при регистрации.
+    pub next: Option<String>,
 }
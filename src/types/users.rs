@@ -1,5 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Serialize)]
@@ -14,9 +15,11 @@ pub struct User {
     pub is_superuser: bool,
     pub thumbnail: Option<String>,
     pub date_joined: DateTime<Utc>,
+    /// Код языка, выбранного пользователем; `None` — язык берётся из запроса.
+    pub locale: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserVisible {
     pub id: Uuid,
     pub email: String,
@@ -37,3 +40,11 @@ pub struct LoggedInUser {
     pub is_staff: bool,
     pub is_superuser: bool,
 }
+
+/// Результат подтверждения регистрации для `Accept: application/json`.
+/// `next` — адрес из токена, если он был задан при регистрации.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ConfirmationResponse {
+    pub message: String,
+    pub next: Option<String>,
+}
@@ -0,0 +1,86 @@
+use crate::metrics::METRICS;
+use crate::settings::SecuritySettings;
+use crate::types::NewAuthEvent;
+use actix_web::http::header::USER_AGENT;
+use actix_web::web::Data;
+use actix_web::HttpRequest;
+use sqlx::PgPool;
+use std::net::{IpAddr, SocketAddr};
+
+/// Записывает событие аутентификации в журнал `auth_events`.
+/// IP-адрес и user-agent берутся из запроса.
+/// Ошибка записи только логируется: аудит не должен ломать сам запрос.
+/// Заодно обновляются счётчики Prometheus по этому событию.
+#[tracing::instrument(name = "Recording auth event", skip(pool, request, event),
+fields(event_type = %event.kind.as_str(), outcome = %event.outcome.as_str()))]
+pub async fn record_auth_event(pool: &PgPool, request: &HttpRequest, event: NewAuthEvent) {
+    METRICS.observe_auth_event(&event);
+
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO auth_events (user_id, event_type, outcome, ip_address, user_agent, details) \
+        VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(event.user_id)
+    .bind(event.kind.as_str())
+    .bind(event.outcome.as_str())
+    .bind(client_ip(request))
+    .bind(user_agent)
+    .bind(event.details)
+    .execute(pool)
+    .await
+    {
+        tracing::event!(target: "sqlx", tracing::Level::ERROR, "Failed to record auth event: {:#?}", e);
+    }
+}
+
+/// Обратные прокси из `security.trusted_proxies`, хранятся в состоянии приложения.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpAddr>);
+
+impl TrustedProxies {
+    pub fn from_settings(settings: &SecuritySettings) -> Result<Self, String> {
+        settings
+            .trusted_proxies
+            .iter()
+            .map(|proxy| {
+                proxy.parse().map_err(|e| {
+                    format!(
+                        "security.trusted_proxies: {} is not an IP address: {}",
+                        proxy, e
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(TrustedProxies)
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.0.contains(&ip)
+    }
+}
+
+/// IP-адрес клиента без порта. Заголовкам `Forwarded`/`X-Forwarded-For` верим, только
+/// если соединение пришло от доверенного прокси, иначе берём адрес самого соединения:
+/// клиент не может подменить IP в журнале аудита.
+pub fn client_ip(request: &HttpRequest) -> Option<String> {
+    let peer = request.peer_addr()?.ip();
+    let from_trusted_proxy = request
+        .app_data::<Data<TrustedProxies>>()
+        .is_some_and(|proxies| proxies.contains(peer));
+    if !from_trusted_proxy {
+        return Some(peer.to_string());
+    }
+
+    let connection_info = request.connection_info();
+    connection_info.realip_remote_addr().map(|addr| {
+        addr.parse::<SocketAddr>()
+            .map(|socket| socket.ip().to_string())
+            .unwrap_or_else(|_| addr.to_string())
+    })
+}
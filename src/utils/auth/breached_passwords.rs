@@ -0,0 +1,415 @@
+use crate::settings::{BreachedPasswordFormat, BreachedPasswordSettings};
+use crate::utils::spawn_blocking_in_span;
+use sha1::{Digest, Sha1};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Сигнатура файла фильтра Блума, который строит `build-hibp-filter`.
+const BLOOM_MAGIC: &[u8; 8] = b"HIBPBLM1";
+
+/// Длина SHA-1 в шестнадцатеричной записи и длина префикса в формате range API.
+const SHA1_HEX_LENGTH: usize = 40;
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+/// Проверка пароля по локальной копии базы Pwned Passwords, без обращения к сети.
+pub enum BreachedPasswordChecker {
+    Disabled,
+    /// Один отсортированный файл строк `SHA1:COUNT`, поиск — двоичный, по смещениям в файле.
+    Sorted(PathBuf),
+    /// Каталог файлов `XXXXX.txt` со строками `SUFFIX:COUNT`, как отдаёт range API.
+    Range(PathBuf),
+    /// Фильтр Блума, целиком загруженный в память.
+    Bloom(BloomFilter),
+}
+
+impl BreachedPasswordChecker {
+    pub fn from_settings(settings: &BreachedPasswordSettings) -> std::io::Result<Self> {
+        if !settings.enabled {
+            return Ok(Self::Disabled);
+        }
+
+        let path = PathBuf::from(&settings.path);
+        match settings.format {
+            BreachedPasswordFormat::Sorted => {
+                File::open(&path)?;
+                Ok(Self::Sorted(path))
+            }
+            BreachedPasswordFormat::Range => {
+                if !path.is_dir() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        format!("{} is not a directory", path.display()),
+                    ));
+                }
+                Ok(Self::Range(path))
+            }
+            BreachedPasswordFormat::Bloom => Ok(Self::Bloom(BloomFilter::read_from(
+                &mut BufReader::new(File::open(&path)?),
+            )?)),
+        }
+    }
+
+    /// Встречается ли пароль в утёкших базах. Функция блокирующая (чтение файлов).
+    pub fn is_breached(&self, password: &str) -> std::io::Result<bool> {
+        let digest: [u8; 20] = Sha1::digest(password.as_bytes()).into();
+
+        match self {
+            Self::Disabled => Ok(false),
+            Self::Sorted(path) => sorted_file_contains(path, &hex::encode_upper(digest)),
+            Self::Range(path) => range_directory_contains(path, &hex::encode_upper(digest)),
+            Self::Bloom(filter) => Ok(filter.contains(&digest)),
+        }
+    }
+}
+
+/// Асинхронная обёртка над `is_breached`, выполняет проверку в пуле блокирующих задач.
+#[tracing::instrument(
+    name = "Checking password against breached corpus",
+    skip(checker, password)
+)]
+pub async fn is_password_breached(
+    checker: Arc<BreachedPasswordChecker>,
+    password: String,
+) -> std::io::Result<bool> {
+    spawn_blocking_in_span(move || checker.is_breached(&password))
+        .await
+        .expect("Unable to unwrap JoinError.")
+}
+
+fn sorted_file_contains(path: &Path, hash: &str) -> std::io::Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut low = 0_u64;
+    let mut high = reader.get_ref().metadata()?.len();
+    let mut line = Vec::new();
+
+    // Двоичный поиск по байтовым смещениям: из середины интервала
+    // переходим к началу ближайшей следующей строки и сравниваем её хэш.
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let line_start = if middle == 0 {
+            reader.seek(SeekFrom::Start(0))?;
+            0
+        } else {
+            // Читаем с `middle - 1`, чтобы не пропустить строку, начинающуюся ровно в `middle`.
+            reader.seek(SeekFrom::Start(middle - 1))?;
+            line.clear();
+            middle - 1 + reader.read_until(b'\n', &mut line)? as u64
+        };
+
+        line.clear();
+        let line_length = reader.read_until(b'\n', &mut line)? as u64;
+        if line_length == 0 {
+            high = middle;
+            continue;
+        }
+
+        let line_hash = line
+            .get(..SHA1_HEX_LENGTH)
+            .map(|bytes| bytes.to_ascii_uppercase())
+            .unwrap_or_default();
+        match line_hash.as_slice().cmp(hash.as_bytes()) {
+            Ordering::Equal => return Ok(true),
+            Ordering::Less => low = line_start + line_length,
+            Ordering::Greater => high = middle,
+        }
+    }
+
+    Ok(false)
+}
+
+fn range_directory_contains(path: &Path, hash: &str) -> std::io::Result<bool> {
+    let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+    let file = match File::open(path.join(format!("{}.txt", prefix))) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let line_suffix = line.split(':').next().unwrap_or_default().trim();
+        if line_suffix.eq_ignore_ascii_case(suffix) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Фильтр Блума по SHA-1 хэшам паролей.
+/// Ложноположительные срабатывания возможны с заданной при построении вероятностью,
+/// ложноотрицательные — нет.
+pub struct BloomFilter {
+    num_hashes: u32,
+    num_bits: u64,
+    bits: Vec<u8>,
+}
+
+impl BloomFilter {
+    /// Создаёт пустой фильтр оптимального размера для `expected_items` элементов.
+    pub fn new(expected_items: u64, false_positive_rate: f64) -> Self {
+        let expected_items = expected_items.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-(expected_items * false_positive_rate.ln()) / (ln2 * ln2)).ceil() as u64;
+        let num_bits = num_bits.max(8);
+        let num_hashes = ((num_bits as f64 / expected_items) * ln2).round().max(1.0) as u32;
+
+        Self {
+            num_hashes,
+            num_bits,
+            bits: vec![0; num_bits.div_ceil(8) as usize],
+        }
+    }
+
+    pub fn insert(&mut self, digest: &[u8; 20]) {
+        for index in self.bit_indexes(digest) {
+            self.bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+    }
+
+    pub fn contains(&self, digest: &[u8; 20]) -> bool {
+        self.bit_indexes(digest)
+            .all(|index| self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0)
+    }
+
+    /// Двойное хэширование: SHA-1 уже равномерно распределён,
+    /// поэтому обе базовые функции берутся прямо из его байтов.
+    fn bit_indexes(&self, digest: &[u8; 20]) -> impl Iterator<Item = u64> {
+        let first = u64::from_le_bytes(digest[0..8].try_into().unwrap());
+        let second = u64::from_le_bytes(digest[8..16].try_into().unwrap()) | 1;
+        let num_bits = self.num_bits;
+
+        (0..self.num_hashes as u64)
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % num_bits)
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(BLOOM_MAGIC)?;
+        writer.write_all(&self.num_hashes.to_le_bytes())?;
+        writer.write_all(&self.num_bits.to_le_bytes())?;
+        writer.write_all(&self.bits)
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<Self> {
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+        let mut magic = [0_u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != BLOOM_MAGIC {
+            return Err(invalid("not a breached password bloom filter file"));
+        }
+
+        let mut num_hashes = [0_u8; 4];
+        reader.read_exact(&mut num_hashes)?;
+        let mut num_bits = [0_u8; 8];
+        reader.read_exact(&mut num_bits)?;
+        let num_hashes = u32::from_le_bytes(num_hashes);
+        let num_bits = u64::from_le_bytes(num_bits);
+        if num_hashes == 0 || num_bits == 0 {
+            return Err(invalid("bloom filter header is corrupted"));
+        }
+
+        let mut bits = vec![0; num_bits.div_ceil(8) as usize];
+        reader.read_exact(&mut bits)?;
+
+        Ok(Self {
+            num_hashes,
+            num_bits,
+            bits,
+        })
+    }
+}
+
+/// Разбирает хэш из строки `SHA1:COUNT` (или `SUFFIX:COUNT` с известным префиксом).
+/// Возвращает хэш и число утечек, если строка корректна.
+pub fn parse_hash_line(prefix: &str, line: &str) -> Option<([u8; 20], u64)> {
+    let (hash, count) = line.trim().split_once(':')?;
+    let digest: [u8; 20] = hex::decode(format!("{}{}", prefix, hash))
+        .ok()?
+        .try_into()
+        .ok()?;
+    Some((digest, count.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Временный файл или каталог, удаляется вместе со значением.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("{}-{}", name, uuid::Uuid::new_v4())))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn sha1_hex(password: &str) -> String {
+        hex::encode_upper(Sha1::digest(password.as_bytes()))
+    }
+
+    fn checker(format: BreachedPasswordFormat, path: &Path) -> BreachedPasswordChecker {
+        BreachedPasswordChecker::from_settings(&BreachedPasswordSettings {
+            enabled: true,
+            format,
+            path: path.to_string_lossy().into_owned(),
+        })
+        .unwrap()
+    }
+
+    /// Отсортированные по хэшу пароли `breached-0`..`breached-N`.
+    fn breached_passwords() -> Vec<(String, String)> {
+        let mut passwords: Vec<_> = (0..64)
+            .map(|i| {
+                let password = format!("breached-{}", i);
+                (sha1_hex(&password), password)
+            })
+            .collect();
+        passwords.sort();
+        passwords
+    }
+
+    #[test]
+    fn sorted_file_finds_first_last_and_middle_lines() {
+        let passwords = breached_passwords();
+        let file = TempPath::new("hibp-sorted");
+        let corpus: String = passwords
+            .iter()
+            .enumerate()
+            .map(|(i, (hash, _))| format!("{}:{}\r\n", hash, i + 1))
+            .collect();
+        std::fs::write(&file.0, corpus).unwrap();
+        let checker = checker(BreachedPasswordFormat::Sorted, &file.0);
+
+        for (_, password) in [
+            &passwords[0],
+            &passwords[passwords.len() / 2],
+            &passwords[passwords.len() - 1],
+        ] {
+            assert!(checker.is_breached(password).unwrap(), "{}", password);
+        }
+        for (_, password) in &passwords {
+            assert!(checker.is_breached(password).unwrap(), "{}", password);
+        }
+        assert!(!checker.is_breached("correct-Horse-battery-42").unwrap());
+    }
+
+    #[test]
+    fn sorted_file_misses_hashes_around_the_corpus() {
+        let file = TempPath::new("hibp-sorted");
+        let middle = sha1_hex("breached");
+        std::fs::write(
+            &file.0,
+            format!("{}:1\n{}:2\n{}:3", "1".repeat(40), middle, "E".repeat(40)),
+        )
+        .unwrap();
+
+        for hash in ["0".repeat(40), "F".repeat(40), "8".repeat(40)] {
+            assert!(!sorted_file_contains(&file.0, &hash).unwrap(), "{}", hash);
+        }
+        // Последняя строка без перевода строки.
+        assert!(sorted_file_contains(&file.0, &"E".repeat(40)).unwrap());
+        assert!(sorted_file_contains(&file.0, &middle).unwrap());
+    }
+
+    #[test]
+    fn range_directory_matches_suffixes_case_insensitively() {
+        let directory = TempPath::new("hibp-range");
+        std::fs::create_dir(&directory.0).unwrap();
+        let hash = sha1_hex("breached");
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+        std::fs::write(
+            directory.0.join(format!("{}.txt", prefix)),
+            format!(
+                "{}:3\r\n{}:12\r\n",
+                "0".repeat(35),
+                suffix.to_ascii_lowercase()
+            ),
+        )
+        .unwrap();
+        let checker = checker(BreachedPasswordFormat::Range, &directory.0);
+
+        assert!(checker.is_breached("breached").unwrap());
+        // Префикс есть, суффикса нет; файла для префикса нет.
+        assert!(
+            !range_directory_contains(&directory.0, &format!("{}{}", prefix, "F".repeat(35)))
+                .unwrap()
+        );
+        assert!(!checker.is_breached("correct-Horse-battery-42").unwrap());
+    }
+
+    #[test]
+    fn hash_lines_are_parsed_with_counts() {
+        let hash = sha1_hex("breached");
+        let digest: [u8; 20] = Sha1::digest(b"breached").into();
+
+        assert_eq!(
+            parse_hash_line("", &format!("{}:42\r\n", hash)),
+            Some((digest, 42))
+        );
+        let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+        assert_eq!(
+            parse_hash_line(prefix, &format!("{}: 7", suffix)),
+            Some((digest, 7))
+        );
+        assert_eq!(
+            parse_hash_line("", &format!("{}:1", hash.to_ascii_lowercase())),
+            Some((digest, 1))
+        );
+
+        for line in [
+            hash.clone(),
+            format!("{}:many", hash),
+            format!("{}:1", &hash[..38]),
+            format!("{}:1", "Z".repeat(40)),
+            String::new(),
+        ] {
+            assert_eq!(parse_hash_line("", &line), None, "{}", line);
+        }
+    }
+
+    #[test]
+    fn bloom_filter_survives_a_round_trip_through_a_file() {
+        let mut filter = BloomFilter::new(64, 0.001);
+        for (_, password) in breached_passwords() {
+            filter.insert(&Sha1::digest(password.as_bytes()).into());
+        }
+        let file = TempPath::new("hibp-bloom");
+        filter
+            .write_to(&mut std::io::BufWriter::new(File::create(&file.0).unwrap()))
+            .unwrap();
+
+        let checker = checker(BreachedPasswordFormat::Bloom, &file.0);
+        for (_, password) in breached_passwords() {
+            assert!(checker.is_breached(&password).unwrap(), "{}", password);
+        }
+        assert!(!checker.is_breached("correct-Horse-battery-42").unwrap());
+    }
+
+    #[test]
+    fn corrupted_bloom_filter_files_are_rejected() {
+        let mut filter = Vec::new();
+        BloomFilter::new(8, 0.01).write_to(&mut filter).unwrap();
+
+        let mut wrong_magic = filter.clone();
+        wrong_magic[..8].copy_from_slice(b"NOTBLOOM");
+        let mut zero_hashes = filter.clone();
+        zero_hashes[8..12].copy_from_slice(&0_u32.to_le_bytes());
+        let truncated = &filter[..filter.len() - 1];
+
+        for bytes in [wrong_magic.as_slice(), zero_hashes.as_slice(), truncated] {
+            assert!(BloomFilter::read_from(&mut &bytes[..]).is_err());
+        }
+        assert!(BloomFilter::read_from(&mut filter.as_slice()).is_ok());
+    }
+}
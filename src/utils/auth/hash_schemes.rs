@@ -0,0 +1,290 @@
+use argon2::password_hash::{Error, PasswordHash, PasswordVerifier};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use pbkdf2::pbkdf2_hmac;
+use scrypt::Scrypt;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use subtle::ConstantTimeEq;
+
+/// Длина ключа, которую Django использует для scrypt.
+const DJANGO_SCRYPT_KEY_LENGTH: usize = 64;
+
+/// Формат хранимого хэша пароля.
+/// Всё, кроме `Argon2`, — унаследованные схемы импортированных пользователей,
+/// такие хэши заменяются на Argon2 при первом успешном входе.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashScheme {
+    /// PHC-строка `$argon2id$...`.
+    Argon2,
+    /// `$2a$`, `$2b$`, `$2x$`, `$2y$` (Rails/Devise).
+    Bcrypt,
+    /// Django `bcrypt$<bcrypt-хэш>`.
+    DjangoBcrypt,
+    /// Django `bcrypt_sha256$<bcrypt-хэш>`: bcrypt от hex SHA-256 пароля.
+    DjangoBcryptSha256,
+    /// Django `pbkdf2_sha256$<iterations>$<salt>$<base64>`.
+    Pbkdf2Sha256,
+    /// Django `pbkdf2_sha1$<iterations>$<salt>$<base64>`.
+    Pbkdf2Sha1,
+    /// PHC-строка `$scrypt$...`.
+    Scrypt,
+    /// Django `scrypt$<n>$<salt>$<r>$<p>$<base64>`.
+    DjangoScrypt,
+    /// `sha1$<salt>$<hex>`, хэш от `salt + password`.
+    SaltedSha1,
+    /// `sha256$<salt>$<hex>`, хэш от `salt + password`.
+    SaltedSha256,
+    /// `sha512$<salt>$<hex>`, хэш от `salt + password`.
+    SaltedSha512,
+}
+
+impl HashScheme {
+    pub fn detect(hash: &str) -> Option<Self> {
+        if hash.starts_with("$argon2") {
+            Some(Self::Argon2)
+        } else if ["$2a$", "$2b$", "$2x$", "$2y$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+        {
+            Some(Self::Bcrypt)
+        } else if hash.starts_with("bcrypt$") {
+            Some(Self::DjangoBcrypt)
+        } else if hash.starts_with("bcrypt_sha256$") {
+            Some(Self::DjangoBcryptSha256)
+        } else if hash.starts_with("pbkdf2_sha256$") {
+            Some(Self::Pbkdf2Sha256)
+        } else if hash.starts_with("pbkdf2_sha1$") {
+            Some(Self::Pbkdf2Sha1)
+        } else if hash.starts_with("$scrypt$") {
+            Some(Self::Scrypt)
+        } else if hash.starts_with("scrypt$") {
+            Some(Self::DjangoScrypt)
+        } else if hash.starts_with("sha1$") {
+            Some(Self::SaltedSha1)
+        } else if hash.starts_with("sha256$") {
+            Some(Self::SaltedSha256)
+        } else if hash.starts_with("sha512$") {
+            Some(Self::SaltedSha512)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        *self != Self::Argon2
+    }
+}
+
+/// Проверяет пароль по хэшу унаследованной схемы.
+/// Для `HashScheme::Argon2` используется `verify_password`.
+pub fn verify_legacy_password(
+    scheme: HashScheme,
+    hash: &str,
+    password: &[u8],
+) -> Result<(), Error> {
+    match scheme {
+        HashScheme::Argon2 => Err(Error::Algorithm),
+        HashScheme::Bcrypt => verify_bcrypt(hash, password),
+        HashScheme::DjangoBcrypt => verify_bcrypt(strip_algorithm(hash)?, password),
+        HashScheme::DjangoBcryptSha256 => {
+            // Django хэширует bcrypt-ом не сам пароль, а hex SHA-256 от него
+            let password = hex::encode(Sha256::digest(password));
+            verify_bcrypt(strip_algorithm(hash)?, password.as_bytes())
+        }
+        HashScheme::Pbkdf2Sha256 => verify_django_pbkdf2(hash, password, pbkdf2_hmac::<Sha256>),
+        HashScheme::Pbkdf2Sha1 => verify_django_pbkdf2(hash, password, pbkdf2_hmac::<Sha1>),
+        HashScheme::Scrypt => Scrypt.verify_password(password, &PasswordHash::new(hash)?),
+        HashScheme::DjangoScrypt => verify_django_scrypt(hash, password),
+        HashScheme::SaltedSha1 => verify_salted_sha::<Sha1>(hash, password),
+        HashScheme::SaltedSha256 => verify_salted_sha::<Sha256>(hash, password),
+        HashScheme::SaltedSha512 => verify_salted_sha::<Sha512>(hash, password),
+    }
+}
+
+fn verify_bcrypt(hash: &str, password: &[u8]) -> Result<(), Error> {
+    match bcrypt::verify(password, hash) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Error::Password),
+        Err(_) => Err(Error::PhcStringField),
+    }
+}
+
+/// Отрезает префикс алгоритма Django: `bcrypt$$2b$...` → `$2b$...`.
+fn strip_algorithm(hash: &str) -> Result<&str, Error> {
+    hash.split_once('$')
+        .map(|(_, rest)| rest)
+        .ok_or(Error::PhcStringField)
+}
+
+/// `derive` — PBKDF2-HMAC с нужной хэш-функцией, например `pbkdf2_hmac::<Sha256>`.
+fn verify_django_pbkdf2(
+    hash: &str,
+    password: &[u8],
+    derive: fn(&[u8], &[u8], u32, &mut [u8]),
+) -> Result<(), Error> {
+    let parts: Vec<&str> = hash.split('$').collect();
+    let [_, iterations, salt, expected] = parts.as_slice() else {
+        return Err(Error::PhcStringField);
+    };
+
+    let iterations: u32 = iterations.parse().map_err(|_| Error::PhcStringField)?;
+    if iterations == 0 {
+        return Err(Error::PhcStringField);
+    }
+    let expected = STANDARD
+        .decode(expected)
+        .map_err(|_| Error::PhcStringField)?;
+
+    let mut computed = vec![0_u8; expected.len()];
+    derive(password, salt.as_bytes(), iterations, &mut computed);
+    constant_time_eq(&computed, &expected)
+}
+
+fn verify_django_scrypt(hash: &str, password: &[u8]) -> Result<(), Error> {
+    let parts: Vec<&str> = hash.split('$').collect();
+    let [_, n, salt, r, p, expected] = parts.as_slice() else {
+        return Err(Error::PhcStringField);
+    };
+
+    let n: u64 = n.parse().map_err(|_| Error::PhcStringField)?;
+    let r: u32 = r.parse().map_err(|_| Error::PhcStringField)?;
+    let p: u32 = p.parse().map_err(|_| Error::PhcStringField)?;
+    if !n.is_power_of_two() {
+        return Err(Error::PhcStringField);
+    }
+    let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, DJANGO_SCRYPT_KEY_LENGTH)
+        .map_err(|_| Error::PhcStringField)?;
+
+    let expected = STANDARD
+        .decode(expected)
+        .map_err(|_| Error::PhcStringField)?;
+    let mut computed = vec![0_u8; expected.len()];
+    scrypt::scrypt(password, salt.as_bytes(), &params, &mut computed)
+        .map_err(|_| Error::PhcStringField)?;
+    constant_time_eq(&computed, &expected)
+}
+
+fn verify_salted_sha<D: Digest>(hash: &str, password: &[u8]) -> Result<(), Error> {
+    let parts: Vec<&str> = hash.split('$').collect();
+    let [_, salt, expected] = parts.as_slice() else {
+        return Err(Error::PhcStringField);
+    };
+    let expected = hex::decode(expected).map_err(|_| Error::PhcStringField)?;
+
+    let mut hasher = D::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(password);
+    constant_time_eq(&hasher.finalize(), &expected)
+}
+
+fn constant_time_eq(computed: &[u8], expected: &[u8]) -> Result<(), Error> {
+    // Пустой ожидаемый хэш совпал бы с чем угодно.
+    if expected.is_empty() {
+        return Err(Error::PhcStringField);
+    }
+    if computed.ct_eq(expected).into() {
+        Ok(())
+    } else {
+        Err(Error::Password)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Пароль из тестов `django.contrib.auth.hashers`: хэши ниже совпадают с `make_password`.
+    const DJANGO_PASSWORD: &str = "lètmein";
+
+    fn verify(hash: &str, password: &str) -> Result<(), Error> {
+        let scheme = HashScheme::detect(hash).expect("Unknown hash scheme.");
+        verify_legacy_password(scheme, hash, password.as_bytes())
+    }
+
+    #[test]
+    fn django_hashes_are_verified() {
+        for hash in [
+            "pbkdf2_sha256$10000$seasalt$CWWFdHOWwPnki7HvkcqN9iA2T3KLW1cf2uZ5kvArtVY=",
+            "pbkdf2_sha1$10000$seasalt$oAfF6vgs95ncksAhGXOWf4Okq7o=",
+            "scrypt$16384$seasalt$8$1$Qj3+9PPyRjSJIebHnG81TMjsqtaIGxNQG/aEB/NYafTJ7tibgfYz71m0ldQESkXFRkdVCBhhY8mx7rQwite/Pw==",
+            "sha1$seasalt$cff36ea83f5706ce9aa7454e63e431fc726b2dc8",
+        ] {
+            assert!(verify(hash, DJANGO_PASSWORD).is_ok(), "{}", hash);
+            assert!(
+                matches!(verify(hash, "letmein"), Err(Error::Password)),
+                "{}",
+                hash
+            );
+        }
+    }
+
+    #[test]
+    fn bcrypt_hashes_from_devise_are_verified() {
+        // Векторы crypt_blowfish (Openwall); Devise хранит bcrypt без префикса.
+        let hash = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+        assert_eq!(HashScheme::detect(hash), Some(HashScheme::Bcrypt));
+        assert!(verify(hash, "U*U").is_ok());
+        assert!(matches!(verify(hash, "U*U*"), Err(Error::Password)));
+
+        let hash = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.VGOzA784oUp/Z0DY336zx7pLYAy0lwK";
+        assert!(verify(hash, "U*U*").is_ok());
+    }
+
+    #[test]
+    fn django_bcrypt_hashes_are_verified() {
+        // `make_password(DJANGO_PASSWORD, "$2b$05$seasaltseasaltseasalt.", hasher)`
+        // для хэшеров `bcrypt` и `bcrypt_sha256`.
+        for (hash, scheme) in [
+            (
+                "bcrypt$$2b$05$seasaltseasaltseasalt.nDbnwbnd94hTTZXvuUG0i2jK8ETMBsu",
+                HashScheme::DjangoBcrypt,
+            ),
+            (
+                "bcrypt_sha256$$2b$05$seasaltseasaltseasalt.2RVA8r5hsSnkuZ56IZ5NIuq963PFx/K",
+                HashScheme::DjangoBcryptSha256,
+            ),
+        ] {
+            assert_eq!(HashScheme::detect(hash), Some(scheme));
+            assert!(verify(hash, DJANGO_PASSWORD).is_ok(), "{}", hash);
+            assert!(
+                matches!(verify(hash, "letmein"), Err(Error::Password)),
+                "{}",
+                hash
+            );
+        }
+    }
+
+    #[test]
+    fn phc_scrypt_and_salted_sha2_hashes_are_verified() {
+        for hash in [
+            "$scrypt$ln=10,r=8,p=1$c2Vhc2FsdC1zZWFzYWx0IQ$UXXTMS+YgGmXXQ0/jkG9c4E97x5rYWyupkMi7tSJMtU",
+            "sha256$seasalt$e0327e0c88846ec7f85601380e86c72a5242e3455a1ae0f736f349858f126eb9",
+            "sha512$seasalt$16bf4502ffdfce9551b90319d06674e6faa3e174144123d392d94470ebf0aa77\
+            096b871f9e84f60ed2bac2f10f755368b068e52547e0435fef8b4f6ca237d7d8",
+        ] {
+            assert!(verify(hash, DJANGO_PASSWORD).is_ok(), "{}", hash);
+            assert!(verify(hash, "letmein").is_err(), "{}", hash);
+        }
+    }
+
+    #[test]
+    fn malformed_hashes_are_rejected() {
+        for hash in [
+            "pbkdf2_sha256$0$seasalt$CWWFdHOWwPnki7HvkcqN9iA2T3KLW1cf2uZ5kvArtVY=",
+            "pbkdf2_sha256$10000$seasalt",
+            "scrypt$1000$seasalt$8$1$Qj3+9PPy",
+            "sha1$seasalt$",
+            "sha1$seasalt$not-hex",
+            "bcrypt$not-a-bcrypt-hash",
+        ] {
+            assert!(
+                matches!(verify(hash, DJANGO_PASSWORD), Err(Error::PhcStringField)),
+                "{}",
+                hash
+            );
+        }
+        assert_eq!(HashScheme::detect("md5$seasalt$abc"), None);
+        assert!(!HashScheme::Argon2.is_legacy());
+    }
+}
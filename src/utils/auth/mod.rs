@@ -1,2 +1,5 @@
+pub mod breached_passwords;
+pub mod hash_schemes;
 pub mod password;
+pub mod password_policy;
 pub mod tokens;
@@ -1,22 +1,154 @@
+use crate::settings::PasswordHashingSettings;
+use crate::utils::auth::hash_schemes::{verify_legacy_password, HashScheme};
+use crate::utils::spawn_blocking_in_span;
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, KeyId, Params, ParamsBuilder, Version,
 };
+use once_cell::sync::OnceCell;
 
-#[tracing::instrument(name = "Hashing user password", skip(password))]
-pub async fn hash(password: &[u8]) -> String {
-    let salt = SaltString::generate(&mut OsRng);
-    Argon2::default()
-        .hash_password(password, &salt)
-        .expect("Unable to hash password.")
-        .to_string()
+/// Идентификатор ключа, которым помечаются хэши, посчитанные с перцем (pepper).
+/// По нему `verify_password` понимает, нужен ли перец для проверки.
+const PEPPER_KEY_ID: &[u8] = b"pepper";
+
+/// Хэш случайного пароля, с которым сверяется пароль при входе несуществующего пользователя.
+/// Так время ответа не выдаёт, существует ли учётная запись.
+/// Считается при первой такой попытке с параметрами из настроек.
+static DUMMY_HASH: OnceCell<String> = OnceCell::new();
+
+/// Собирает `Argon2` с алгоритмом, версией, стоимостью и перцем из настроек.
+fn hasher(settings: &PasswordHashingSettings) -> Argon2<'_> {
+    let algorithm: Algorithm = settings
+        .algorithm
+        .parse()
+        .expect("Unsupported password hashing algorithm.");
+    let version =
+        Version::try_from(settings.version).expect("Unsupported password hashing version.");
+
+    let mut params = ParamsBuilder::new();
+    params
+        .m_cost(settings.m_cost)
+        .t_cost(settings.t_cost)
+        .p_cost(settings.p_cost);
+
+    match &settings.pepper {
+        Some(pepper) => {
+            params.keyid(KeyId::new(PEPPER_KEY_ID).expect("Invalid pepper key id."));
+            let params = params
+                .build()
+                .expect("Invalid password hashing parameters.");
+            Argon2::new_with_secret(pepper.as_bytes(), algorithm, version, params)
+                .expect("Invalid password hashing pepper.")
+        }
+        None => Argon2::new(
+            algorithm,
+            version,
+            params
+                .build()
+                .expect("Invalid password hashing parameters."),
+        ),
+    }
+}
+
+/// Хэширование так же ресурсоёмко, как проверка, поэтому выполняется в `spawn_blocking`
+/// и не занимает поток обработчиков: при регистрации, пересчёте хэша после входа и т.п.
+#[tracing::instrument(name = "Hashing user password", skip(password, settings))]
+pub async fn hash(password: &[u8], settings: &PasswordHashingSettings) -> String {
+    let settings = settings.clone();
+    let password = password.to_vec();
+    spawn_blocking_in_span(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        hasher(&settings)
+            .hash_password(&password, &salt)
+            .expect("Unable to hash password.")
+            .to_string()
+    })
+    .await
+    .expect("Unable to unwrap JoinError.")
 }
 
-#[tracing::instrument(name = "Verifying user password", skip(password, hash))]
-pub async fn verify_password(
+/// Проверка хэша ресурсоёмкая, поэтому функция синхронная
+/// и вызывается внутри `spawn_blocking`.
+/// Схема определяется по формату хэша: кроме Argon2 поддерживаются хэши импортированных
+/// пользователей (bcrypt, PBKDF2, scrypt, SHA с солью), см. `HashScheme`.
+#[tracing::instrument(name = "Verifying user password", skip(password, hash, settings))]
+pub fn verify_password(
     hash: &str,
     password: &[u8],
+    settings: &PasswordHashingSettings,
+) -> Result<(), argon2::password_hash::Error> {
+    match HashScheme::detect(hash) {
+        Some(HashScheme::Argon2) => verify_argon2_password(hash, password, settings),
+        Some(scheme) => verify_legacy_password(scheme, hash, password),
+        None => Err(argon2::password_hash::Error::Algorithm),
+    }
+}
+
+/// Параметры берутся из самого хэша, перец — только если хэш им помечен.
+fn verify_argon2_password(
+    hash: &str,
+    password: &[u8],
+    settings: &PasswordHashingSettings,
 ) -> Result<(), argon2::password_hash::Error> {
     let parsed_hash = PasswordHash::new(hash)?;
-    Argon2::default().verify_password(password, &parsed_hash)
+    let params = Params::try_from(&parsed_hash)?;
+
+    if params.keyid() == PEPPER_KEY_ID {
+        let pepper = settings
+            .pepper
+            .as_ref()
+            .ok_or(argon2::password_hash::Error::Crypto)?;
+        Argon2::new_with_secret(
+            pepper.as_bytes(),
+            Algorithm::default(),
+            Version::default(),
+            params,
+        )?
+        .verify_password(password, &parsed_hash)
+    } else {
+        Argon2::default().verify_password(password, &parsed_hash)
+    }
+}
+
+/// Выполняет такую же по стоимости проверку, как `verify_password`, результат отбрасывается.
+#[tracing::instrument(name = "Verifying dummy password", skip(password, settings))]
+pub fn verify_dummy_password(password: &[u8], settings: &PasswordHashingSettings) {
+    let dummy_hash = DUMMY_HASH.get_or_init(|| {
+        let salt = SaltString::generate(&mut OsRng);
+        hasher(settings)
+            .hash_password(salt.as_str().as_bytes(), &salt)
+            .expect("Unable to hash dummy password.")
+            .to_string()
+    });
+    let _ = verify_password(dummy_hash, password, settings);
+}
+
+/// Хэш посчитан с параметрами слабее текущих: унаследованной схемой, другим алгоритмом
+/// или более старой версией, с меньшей стоимостью по памяти, времени или параллелизму,
+/// либо без перца, когда он задан.
+/// Такой хэш пересчитывается при следующем успешном входе.
+pub fn needs_rehash(hash: &str, config: &PasswordHashingSettings) -> bool {
+    if HashScheme::detect(hash) != Some(HashScheme::Argon2) {
+        return true;
+    }
+
+    let parsed_hash = match PasswordHash::new(hash) {
+        Ok(parsed_hash) => parsed_hash,
+        Err(_) => return true,
+    };
+    let params = match Params::try_from(&parsed_hash) {
+        Ok(params) => params,
+        Err(_) => return true,
+    };
+
+    let algorithm_changed = parsed_hash.algorithm.as_str() != config.algorithm;
+    let version_older = parsed_hash.version.unwrap_or(0) < config.version;
+    let missing_pepper = config.pepper.is_some() && params.keyid() != PEPPER_KEY_ID;
+
+    algorithm_changed
+        || version_older
+        || missing_pepper
+        || params.m_cost() < config.m_cost
+        || params.t_cost() < config.t_cost
+        || params.p_cost() < config.p_cost
 }
@@ -0,0 +1,293 @@
+use crate::i18n::{t, t_with, Locale};
+use crate::settings::PasswordPolicy;
+use crate::types::FieldError;
+
+/// Минимальная длина фрагмента личных данных, который ищется в пароле.
+/// Более короткие фрагменты (например, имя "Li") дают слишком много ложных срабатываний.
+const MIN_PERSONAL_FRAGMENT_LENGTH: usize = 3;
+
+/// Проверяет пароль по политике и возвращает все нарушенные правила сразу.
+/// `user_inputs` — email, имя и фамилия пользователя, `locale` — язык сообщений.
+#[tracing::instrument(
+    name = "Validating password against policy",
+    skip(policy, password, user_inputs)
+)]
+pub fn validate_password(
+    policy: &PasswordPolicy,
+    password: &str,
+    user_inputs: &[&str],
+    locale: Locale,
+) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let length = password.chars().count();
+
+    if length < policy.min_length {
+        errors.push(field_error(
+            "password_too_short",
+            t_with(locale, "password.too_short", &[("min", &policy.min_length)]),
+        ));
+    }
+    if length > policy.max_length {
+        errors.push(field_error(
+            "password_too_long",
+            t_with(locale, "password.too_long", &[("max", &policy.max_length)]),
+        ));
+    }
+    if policy.require_lowercase && !password.chars().any(char::is_lowercase) {
+        errors.push(field_error(
+            "password_missing_lowercase",
+            t(locale, "password.missing_lowercase"),
+        ));
+    }
+    if policy.require_uppercase && !password.chars().any(char::is_uppercase) {
+        errors.push(field_error(
+            "password_missing_uppercase",
+            t(locale, "password.missing_uppercase"),
+        ));
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        errors.push(field_error(
+            "password_missing_digit",
+            t(locale, "password.missing_digit"),
+        ));
+    }
+    if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+        errors.push(field_error(
+            "password_missing_symbol",
+            t(locale, "password.missing_symbol"),
+        ));
+    }
+    if policy.reject_personal_info && contains_personal_info(password, user_inputs) {
+        errors.push(field_error(
+            "password_contains_personal_info",
+            t(locale, "password.contains_personal_info"),
+        ));
+    }
+
+    // zxcvbn отказывается оценивать пустую строку, её уже отсекает `min_length`.
+    // Слишком длинный пароль тоже не оцениваем: это дорого, а он и так будет отклонён.
+    if !password.is_empty() && length <= policy.max_length {
+        if let Ok(estimate) = zxcvbn::zxcvbn(password, user_inputs) {
+            if estimate.score() < policy.min_strength_score {
+                errors.push(field_error(
+                    "password_too_weak",
+                    t_with(
+                        locale,
+                        "password.too_weak",
+                        &[
+                            ("score", &estimate.score()),
+                            ("required", &policy.min_strength_score),
+                        ],
+                    ),
+                ));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn contains_personal_info(password: &str, user_inputs: &[&str]) -> bool {
+    let password = password.to_lowercase();
+
+    user_inputs
+        .iter()
+        .flat_map(|input| {
+            let input = input.trim().to_lowercase();
+            // Для email проверяем и адрес целиком, и его локальную часть.
+            let local_part = input.split('@').next().map(str::to_string);
+            std::iter::once(input).chain(local_part)
+        })
+        .filter(|fragment| fragment.chars().count() >= MIN_PERSONAL_FRAGMENT_LENGTH)
+        .any(|fragment| password.contains(&fragment))
+}
+
+fn field_error(code: &str, message: String) -> FieldError {
+    FieldError {
+        code: code.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Политика без требований: каждый тест включает только проверяемое правило.
+    fn lenient_policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 1,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            min_strength_score: 0,
+            reject_personal_info: false,
+        }
+    }
+
+    /// Включает правило, пароль, который его нарушает, пароль, который проходит, и код ошибки.
+    type RuleCase = (
+        fn(&mut PasswordPolicy),
+        &'static str,
+        &'static str,
+        &'static str,
+    );
+
+    fn violations(policy: &PasswordPolicy, password: &str, user_inputs: &[&str]) -> Vec<String> {
+        match validate_password(policy, password, user_inputs, Locale::En) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.into_iter().map(|error| error.code).collect(),
+        }
+    }
+
+    #[test]
+    fn each_rule_is_reported_with_its_code() {
+        let cases: [RuleCase; 6] = [
+            (
+                |p| p.min_length = 12,
+                "short-pass",
+                "twelve-chars",
+                "password_too_short",
+            ),
+            (
+                |p| p.max_length = 8,
+                "nine-char",
+                "8-chars!",
+                "password_too_long",
+            ),
+            (
+                |p| p.require_lowercase = true,
+                "ALL-CAPS-1",
+                "Mixed-case-1",
+                "password_missing_lowercase",
+            ),
+            (
+                |p| p.require_uppercase = true,
+                "no-capitals",
+                "One-Capital",
+                "password_missing_uppercase",
+            ),
+            (
+                |p| p.require_digit = true,
+                "no-digits-here",
+                "one-digit-1",
+                "password_missing_digit",
+            ),
+            (
+                |p| p.require_symbol = true,
+                "NoSymbols42",
+                "Symbol!42",
+                "password_missing_symbol",
+            ),
+        ];
+
+        for (enable, failing, passing, code) in cases {
+            let mut policy = lenient_policy();
+            enable(&mut policy);
+            assert_eq!(violations(&policy, failing, &[]), vec![code], "{}", failing);
+            assert!(violations(&policy, passing, &[]).is_empty(), "{}", passing);
+        }
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        let mut policy = lenient_policy();
+        policy.min_length = 6;
+        policy.max_length = 6;
+        // 6 символов, но 12 байт.
+        assert!(violations(&policy, "пароль", &[]).is_empty());
+    }
+
+    #[test]
+    fn all_violations_are_reported_together() {
+        let policy = PasswordPolicy {
+            min_length: 12,
+            max_length: 128,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            min_strength_score: 3,
+            reject_personal_info: true,
+        };
+
+        assert_eq!(
+            violations(&policy, "ivanov", &["ivanov@example.com", "Ivan", "Ivanov"]),
+            vec![
+                "password_too_short",
+                "password_missing_uppercase",
+                "password_missing_digit",
+                "password_missing_symbol",
+                "password_contains_personal_info",
+                "password_too_weak",
+            ]
+        );
+    }
+
+    #[test]
+    fn weak_passwords_are_rejected_by_strength_score() {
+        let mut policy = lenient_policy();
+        policy.min_strength_score = 3;
+
+        for password in ["password", "qwerty123", "aaaaaaaaaaaa"] {
+            assert_eq!(
+                violations(&policy, password, &[]),
+                vec!["password_too_weak"],
+                "{}",
+                password
+            );
+        }
+        assert!(violations(&policy, "correct-Horse-battery-42", &[]).is_empty());
+
+        // Пароль из данных пользователя zxcvbn оценивает ниже.
+        policy.min_strength_score = 4;
+        assert!(violations(&policy, "Quetzalcoatl-Ivanovich", &[]).is_empty());
+        assert_eq!(
+            violations(
+                &policy,
+                "Quetzalcoatl-Ivanovich",
+                &["Quetzalcoatl", "Ivanovich"]
+            ),
+            vec!["password_too_weak"]
+        );
+    }
+
+    #[test]
+    fn personal_info_is_matched_case_insensitively() {
+        let user_inputs = ["John.Smith@Example.com", "John", "Smith"];
+
+        assert!(contains_personal_info("my-SMITH-password", &user_inputs));
+        assert!(contains_personal_info("xxjohn.smithxx", &user_inputs));
+        assert!(contains_personal_info(
+            "JOHN.SMITH@EXAMPLE.COM!",
+            &user_inputs
+        ));
+        assert!(!contains_personal_info(
+            "correct-Horse-battery-42",
+            &user_inputs
+        ));
+    }
+
+    #[test]
+    fn personal_info_fragments_below_minimum_length_are_ignored() {
+        // "Li" и "Wu" короче минимума, "Lin" — ровно минимум.
+        assert!(!contains_personal_info("alibi-would-work", &["Li", "Wu"]));
+        assert!(!contains_personal_info("ab-password", &["ab@example.com"]));
+        assert!(contains_personal_info("berlin-2024", &["Lin"]));
+        assert!(!contains_personal_info("anything", &["  ", ""]));
+
+        let mut policy = lenient_policy();
+        policy.reject_personal_info = true;
+        assert!(violations(&policy, "alibi-would-work", &["Li", "Wu"]).is_empty());
+        assert_eq!(
+            violations(&policy, "berlin-2024", &["Lin"]),
+            vec!["password_contains_personal_info"]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::metrics::METRICS;
 use crate::settings::get_settings;
 use crate::types::ConfirmationToken;
 use argon2::password_hash::rand_core::{OsRng, RngCore};
@@ -17,12 +18,15 @@ const SESSION_KEY_PREFIX: &str = "valid_session_key_for_{}";
 /// Этот ключ используется для уничтожения токена как только он будет подтвержден.
 /// В зависимости от его использования, у выданного токена срок жизни не более часа.
 /// Что означает, что он уничтожается по истечении срока его службы.
+/// `next` — адрес, куда перенаправить пользователя после подтверждения; токен зашифрован
+/// и аутентифицирован, поэтому подменить его в ссылке нельзя.
 
 #[tracing::instrument(name = "Issue pasetors token", skip(redis_connection))]
 pub async fn issue_confirmation_token_pasetors(
     user_id: uuid::Uuid,
     redis_connection: &mut deadpool_redis::redis::aio::Connection,
     is_for_password_change: Option<bool>,
+    next: Option<&str>,
 ) -> Result<String, deadpool_redis::redis::RedisError> {
     // Генерируем 128 байт случайных данных для сеансового ключа
     let session_key: String = {
@@ -43,7 +47,7 @@ pub async fn issue_confirmation_token_pasetors(
     };
 
     redis_connection
-        .set(
+        .set::<_, _, ()>(
             redis_key.clone(), // Подтверждаем, что ключ существует, чтобы указать, что сеанс "живой".
             String::new(),
         )
@@ -75,7 +79,7 @@ pub async fn issue_confirmation_token_pasetors(
     };
 
     redis_connection
-        .expire(
+        .expire::<_, ()>(
             redis_key.clone(),
             time_to_live.num_seconds().try_into().unwrap(),
         )
@@ -91,6 +95,9 @@ pub async fn issue_confirmation_token_pasetors(
     claims
         .add_additional("session_key", json!(session_key))
         .unwrap();
+    if let Some(next) = next {
+        claims.add_additional("next", json!(next)).unwrap();
+    }
 
     let sk = SymmetricKey::<V4>::from(settings.secret.secret_key.as_bytes()).unwrap();
     Ok(local::encrypt(
@@ -117,7 +124,10 @@ pub async fn verify_confirmation_token_pasetor(
 
     let validation_rules = ClaimsValidationRules::new();
     let untrusted_token = UntrustedToken::<pasetors::token::Local, V4>::try_from(&token) //проверить написание pasetors::token::Local, в исходнике только Local
-        .map_err(|e| format!("TokenValidation: {}", e))?;
+        .map_err(|e| {
+            METRICS.token_verification_failed("malformed");
+            format!("TokenValidation: {}", e)
+        })?;
 
     let trusted_token = local::decrypt(
         &sk,
@@ -126,7 +136,11 @@ pub async fn verify_confirmation_token_pasetor(
         None,
         Some(settings.secret.hmac_secret.as_bytes()),
     )
-    .map_err(|e| format!("Pasetor: {}", e))?;
+    .map_err(|e| {
+        // Сюда же попадают токены с истёкшим `exp`.
+        METRICS.token_verification_failed("invalid_or_expired");
+        format!("Pasetor: {}", e)
+    })?;
     let claims = trusted_token.payload_claims().unwrap();
 
     let uid = serde_json::to_value(claims.get_claim("user_id").unwrap()).unwrap();
@@ -138,7 +152,10 @@ pub async fn verify_confirmation_token_pasetor(
                     serde_json::to_value(claims.get_claim("session_key").unwrap()).unwrap();
                 let session_key = match serde_json::from_value::<String>(sss_key) {
                     Ok(session_key) => session_key,
-                    Err(e) => return Err(format!("{}", e)),
+                    Err(e) => {
+                        METRICS.token_verification_failed("invalid_claims");
+                        return Err(format!("{}", e));
+                    }
                 };
 
                 let redis_key = {
@@ -155,19 +172,39 @@ pub async fn verify_confirmation_token_pasetor(
                 if redis_connection
                     .get::<_, Option<String>>(redis_key.clone())
                     .await
-                    .map_err(|e| format!("{}", e))?
+                    .map_err(|e| {
+                        METRICS.token_verification_failed("redis_error");
+                        format!("{}", e)
+                    })?
                     .is_none()
                 {
+                    METRICS.token_verification_failed("used_or_expired");
                     return Err("Token has been used or expired.".to_string());
                 }
                 redis_connection
-                    .del(redis_key.clone())
+                    .del::<_, ()>(redis_key.clone())
                     .await
-                    .map_err(|e| format!("{}", e))?;
-                Ok(ConfirmationToken { user_id: user_uuid })
+                    .map_err(|e| {
+                        METRICS.token_verification_failed("redis_error");
+                        format!("{}", e)
+                    })?;
+                let next = claims
+                    .get_claim("next")
+                    .and_then(|next| next.as_str())
+                    .map(str::to_string);
+                Ok(ConfirmationToken {
+                    user_id: user_uuid,
+                    next,
+                })
+            }
+            Err(e) => {
+                METRICS.token_verification_failed("invalid_claims");
+                Err(format!("{}", e))
             }
-            Err(e) => Err(format!("{}", e)),
         },
-        Err(e) => Err(format!("{}", e)),
+        Err(e) => {
+            METRICS.token_verification_failed("invalid_claims");
+            Err(format!("{}", e))
+        }
     }
 }
@@ -0,0 +1,54 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_util::task::TaskTracker;
+use tracing::Instrument;
+
+/// Фоновые задачи одного `Application` (отправка писем и т.п.), которые нужно дождаться
+/// при его остановке. Хранится в состоянии приложения, обработчики получают его
+/// как `Data<BackgroundTasks>`; у каждого приложения в процессе (например, в тестах) свой набор.
+#[derive(Clone, Default)]
+pub struct BackgroundTasks(TaskTracker);
+
+impl BackgroundTasks {
+    /// Запускает задачу в фоне так, чтобы `drain` её дождался.
+    /// Задача выполняется в текущем span, чтобы её логи были связаны с запросом.
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.0.spawn(future.instrument(tracing::Span::current()))
+    }
+
+    /// Ждёт завершения фоновых задач не дольше `deadline`.
+    /// Возвращает `false`, если к сроку остались незавершённые задачи.
+    pub async fn drain(&self, deadline: Duration) -> bool {
+        self.0.close();
+        let pending = self.0.len();
+        if pending > 0 {
+            tracing::event!(target: "backend", tracing::Level::INFO, "Waiting for {} background task(s) to finish", pending);
+        }
+
+        match tokio::time::timeout(deadline, self.0.wait()).await {
+            Ok(()) => true,
+            Err(_) => {
+                tracing::event!(target: "backend", tracing::Level::ERROR,
+                    "{} background task(s) did not finish within {:?} and will be dropped",
+                    self.0.len(), deadline);
+                false
+            }
+        }
+    }
+}
+
+/// `spawn_blocking` в текущем span: синхронная работа (проверка хэша пароля и т.п.)
+/// остаётся в логах связанной с запросом.
+pub fn spawn_blocking_in_span<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = tracing::Span::current();
+    tokio::task::spawn_blocking(move || span.in_scope(f))
+}
@@ -0,0 +1,62 @@
+use crate::settings::Settings;
+
+/// Origin адреса (`https://app.example.com:8443`) без пути; `None`, если адрес не разбирается
+/// или у него нет origin (например, `data:`).
+pub fn origin_of(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .map(|url| url.origin().ascii_serialization())
+        .filter(|origin| origin != "null")
+}
+
+/// Разрешён ли origin настройками `cors`: это origin `frontend_url`,
+/// один из `allowed_origins` или он подходит под шаблон из `allowed_origin_patterns`.
+pub fn is_allowed_origin(origin: &str, settings: &Settings) -> bool {
+    let origin = origin.trim_end_matches('/').to_lowercase();
+
+    origin_of(&settings.frontend_url).as_deref() == Some(origin.as_str())
+        || settings
+            .cors
+            .allowed_origins
+            .iter()
+            .any(|allowed| allowed.trim_end_matches('/').eq_ignore_ascii_case(&origin))
+        || settings
+            .cors
+            .allowed_origin_patterns
+            .iter()
+            .any(|pattern| matches_pattern(&origin, &pattern.to_lowercase()))
+}
+
+/// Адрес на фронтенде: путь, начинающийся с `/`, дополняется `frontend_url`,
+/// полный адрес возвращается как есть.
+pub fn resolve_frontend_url(target: &str, frontend_url: &str) -> String {
+    if target.starts_with('/') {
+        format!("{}{}", frontend_url.trim_end_matches('/'), target)
+    } else {
+        target.to_string()
+    }
+}
+
+/// Можно ли перенаправить пользователя по этому адресу: его origin должен быть разрешён CORS.
+pub fn is_allowed_redirect(url: &str, settings: &Settings) -> bool {
+    origin_of(url).is_some_and(|origin| is_allowed_origin(&origin, settings))
+}
+
+/// `*` в шаблоне заменяет один или несколько поддоменов, но не схему, порт или путь.
+fn matches_pattern(origin: &str, pattern: &str) -> bool {
+    let subdomain = pattern
+        .split_once('*')
+        .and_then(|(prefix, suffix)| origin.strip_prefix(prefix)?.strip_suffix(suffix));
+
+    match subdomain {
+        Some(subdomain) => {
+            !subdomain.is_empty()
+                && !subdomain.starts_with('.')
+                && !subdomain.ends_with('.')
+                && subdomain
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,34 @@
+use crate::types::CSRF_TOKEN_KEY;
+use actix_session::Session;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use subtle::ConstantTimeEq;
+
+/// Заголовок, в котором клиент возвращает CSRF-токен своей сессии.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+/// CSRF-токен текущей сессии; создаётся при первом обращении и хранится в самой сессии
+/// до выхода или следующего входа.
+pub fn session_csrf_token(session: &Session) -> Result<String, String> {
+    if let Some(token) = session
+        .get::<String>(CSRF_TOKEN_KEY)
+        .map_err(|e| format!("{}", e))?
+    {
+        return Ok(token);
+    }
+
+    let mut bytes = [0_u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let token = hex::encode(bytes);
+    session
+        .insert(CSRF_TOKEN_KEY, &token)
+        .map_err(|e| format!("{}", e))?;
+    Ok(token)
+}
+
+/// Сравнивает токен из заголовка с токеном сессии за постоянное время.
+pub fn verify_csrf_token(session: &Session, provided: &str) -> bool {
+    match session.get::<String>(CSRF_TOKEN_KEY) {
+        Ok(Some(expected)) => expected.as_bytes().ct_eq(provided.as_bytes()).into(),
+        _ => false,
+    }
+}
@@ -0,0 +1,203 @@
+use crate::settings::EmailNormalizationSettings;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+
+/// Домены Gmail: точки в локальной части не значимы, а `googlemail.com` — синоним `gmail.com`.
+const GMAIL_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
+/// Адрес электронной почты в двух видах.
+/// `address` — то, что показывается пользователю и куда отправляются письма:
+/// обрезанные пробелы, домен в нижнем регистре и в punycode, локальная часть без изменений.
+/// `canonical` — ключ учётной записи (`users.email_normalized`): весь адрес в нижнем регистре
+/// плюс правила провайдеров из настроек. Все поиски пользователя по email идут по нему.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedEmail {
+    pub address: String,
+    pub canonical: String,
+}
+
+pub fn normalize_email(
+    raw: &str,
+    settings: &EmailNormalizationSettings,
+) -> Result<NormalizedEmail, String> {
+    let trimmed = raw.trim();
+    let (local_part, domain) = trimmed
+        .rsplit_once('@')
+        .filter(|(local_part, domain)| !local_part.is_empty() && !domain.is_empty())
+        .ok_or_else(|| format!("{} is not a valid email address", trimmed))?;
+
+    // `domain_to_ascii` приводит домен к нижнему регистру и переводит IDN в punycode.
+    let domain = idna::domain_to_ascii(domain.trim_end_matches('.'))
+        .map_err(|e| format!("Invalid email domain: {:?}", e))?;
+    let address = format!("{}@{}", local_part, domain);
+
+    let mut canonical_local = local_part.to_lowercase();
+    let mut canonical_domain = domain;
+
+    let is_gmail = GMAIL_DOMAINS.contains(&canonical_domain.as_str());
+    if settings.strip_plus_tags || (settings.gmail_rules && is_gmail) {
+        if let Some((base, _tag)) = canonical_local.split_once('+') {
+            canonical_local = base.to_string();
+        }
+    }
+    if settings.gmail_rules && is_gmail {
+        canonical_local = canonical_local.replace('.', "");
+        canonical_domain = GMAIL_DOMAINS[0].to_string();
+    }
+    if canonical_local.is_empty() {
+        return Err(format!("{} is not a valid email address", trimmed));
+    }
+
+    Ok(NormalizedEmail {
+        address,
+        canonical: format!("{}@{}", canonical_local, canonical_domain),
+    })
+}
+
+/// Итог `renormalize_emails`.
+/// `conflicts` — адреса, которые не удалось обновить: их канонический вид уже занят
+/// другой учётной записью или сам адрес некорректен. Такие записи нужно разобрать вручную.
+#[derive(Debug, Default)]
+pub struct EmailRenormalization {
+    pub updated: u64,
+    pub conflicts: Vec<String>,
+}
+
+/// Пересчитывает `users.email_normalized` по текущим правилам `email_normalization`.
+/// Нужен после миграции, которая заполнила столбец только `lower(trim(email))`,
+/// и после смены правил в настройках: иначе вход ищет ключ, которого нет в таблице.
+/// Запускается только явно, командой `backend-admin normalize-emails`: проход по всей таблице
+/// не должен повторяться при каждом запуске каждого экземпляра.
+#[tracing::instrument(name = "Re-normalizing stored emails", skip(pool, settings))]
+pub async fn renormalize_emails(
+    pool: &PgPool,
+    settings: &EmailNormalizationSettings,
+    dry_run: bool,
+) -> Result<EmailRenormalization, sqlx::Error> {
+    let users = sqlx::query("SELECT id, email, email_normalized FROM users")
+        .map(|row: PgRow| -> (uuid::Uuid, String, String) {
+            (row.get("id"), row.get("email"), row.get("email_normalized"))
+        })
+        .fetch_all(pool)
+        .await?;
+
+    let mut report = EmailRenormalization::default();
+    for (user_id, email, stored) in users {
+        let canonical = match normalize_email(&email, settings) {
+            Ok(normalized) => normalized.canonical,
+            Err(e) => {
+                report.conflicts.push(format!("{}: {}", email, e));
+                continue;
+            }
+        };
+        if canonical == stored {
+            continue;
+        }
+        if dry_run {
+            report.updated += 1;
+            continue;
+        }
+
+        match sqlx::query("UPDATE users SET email_normalized = $1 WHERE id = $2")
+            .bind(&canonical)
+            .bind(user_id)
+            .execute(pool)
+            .await
+        {
+            Ok(_) => report.updated += 1,
+            // Нарушение `users_email_normalized_key`: адрес совпал с другой учётной записью.
+            Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505") => {
+                report
+                    .conflicts
+                    .push(format!("{}: {} belongs to another user", email, canonical));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(strip_plus_tags: bool, gmail_rules: bool) -> EmailNormalizationSettings {
+        EmailNormalizationSettings {
+            strip_plus_tags,
+            gmail_rules,
+        }
+    }
+
+    fn canonical(raw: &str, settings: &EmailNormalizationSettings) -> String {
+        normalize_email(raw, settings).unwrap().canonical
+    }
+
+    #[test]
+    fn address_keeps_local_part_and_lowercases_domain() {
+        let email =
+            normalize_email("  John.Smith+news@Example.COM ", &settings(false, true)).unwrap();
+        assert_eq!(email.address, "John.Smith+news@example.com");
+        assert_eq!(email.canonical, "john.smith+news@example.com");
+    }
+
+    #[test]
+    fn gmail_rules_drop_dots_and_tags_and_merge_googlemail() {
+        let rules = settings(false, true);
+        assert_eq!(
+            canonical("John.Smith@gmail.com", &rules),
+            "johnsmith@gmail.com"
+        );
+        assert_eq!(
+            canonical("j.o.h.n.smith+spam@GMAIL.com", &rules),
+            "johnsmith@gmail.com"
+        );
+        assert_eq!(
+            canonical("johnsmith@googlemail.com", &rules),
+            "johnsmith@gmail.com"
+        );
+
+        let no_rules = settings(false, false);
+        assert_eq!(
+            canonical("John.Smith@gmail.com", &no_rules),
+            "john.smith@gmail.com"
+        );
+        assert_eq!(
+            canonical("john@googlemail.com", &no_rules),
+            "john@googlemail.com"
+        );
+    }
+
+    #[test]
+    fn plus_tags_are_stripped_for_every_domain_only_when_enabled() {
+        assert_eq!(
+            canonical("ann+shop@example.com", &settings(true, false)),
+            "ann@example.com"
+        );
+        assert_eq!(
+            canonical("ann+shop@example.com", &settings(false, true)),
+            "ann+shop@example.com"
+        );
+    }
+
+    #[test]
+    fn international_domains_are_converted_to_punycode() {
+        let email = normalize_email("Иван@Пример.РФ.", &settings(false, true)).unwrap();
+        assert_eq!(email.address, "Иван@xn--e1afmkfd.xn--p1ai");
+        assert_eq!(email.canonical, "иван@xn--e1afmkfd.xn--p1ai");
+    }
+
+    #[test]
+    fn invalid_addresses_are_rejected() {
+        let rules = settings(true, true);
+        for raw in [
+            "",
+            "no-at-sign",
+            "@example.com",
+            "user@",
+            "+tag@example.com",
+            "...@gmail.com",
+        ] {
+            assert!(normalize_email(raw, &rules).is_err(), "{}", raw);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Шаблоны писем: пары `emails/<имя>.html` и `emails/<имя>.txt` с общим макетом `emails/base.*`.
+//! Перевод письма лежит рядом (`emails/<имя>.ru.html`), тема берётся из каталога сообщений.
+//! Служебные страницы (`dev/*.html`) рендерятся в том же окружении.
+use crate::i18n::{t, t_with, Locale};
+use crate::settings::Settings;
+use minijinja::value::Value;
+use minijinja::{Environment, ErrorKind, Source};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+/// Общее окружение шаблонов, создаётся при первом рендере по настройкам вызывающего.
+static TEMPLATES: OnceCell<Environment<'static>> = OnceCell::new();
+
+/// Готовое письмо: тема, HTML и текстовая часть.
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+/// Переменные `brand` в шаблонах.
+#[derive(Serialize)]
+struct BrandContext<'a> {
+    name: &'a str,
+    url: &'a str,
+    logo_url: Option<&'a str>,
+    support_email: Option<&'a str>,
+    accent_color: &'a str,
+}
+
+/// Окружение minijinja, которое читает шаблоны из `email.templates_dir`.
+/// Шаблон компилируется при первом обращении и дальше берётся из памяти окружения,
+/// поэтому для перечитывания с диска нужно новое окружение.
+fn template_environment(settings: &Settings) -> Environment<'static> {
+    let brand = &settings.brand;
+    let mut env = Environment::new();
+    env.set_source(Source::from_path(&settings.email.templates_dir));
+    env.add_global(
+        "brand",
+        Value::from_serializable(&BrandContext {
+            name: &brand.name,
+            url: brand.url.as_deref().unwrap_or(&settings.frontend_url),
+            logo_url: brand.logo_url.as_deref(),
+            support_email: brand.support_email.as_deref(),
+            accent_color: &brand.accent_color,
+        }),
+    );
+    // Общие для всех писем строки макета берутся из каталога: `{{ t(lang, "email.footer.reason") }}`
+    env.add_function("t", |lang: String, code: String| {
+        t(Locale::from_tag(&lang).unwrap_or_default(), &code)
+    });
+    env
+}
+
+/// Рендерит письмо `template` на языке получателя. В шаблоны, кроме `vars`,
+/// передаются `lang`, `title` (тема письма) и глобальная `brand`.
+/// С `email.reload_templates` шаблоны перечитываются с диска при каждом вызове.
+pub fn render_email<T: Serialize>(
+    template: &str,
+    locale: Locale,
+    vars: T,
+    settings: &Settings,
+) -> Result<RenderedEmail, String> {
+    let subject = t_with(
+        locale,
+        &format!("email.{}.subject", template_key(template)),
+        &[("brand", &settings.brand.name)],
+    );
+
+    let mut ctx = serde_json::to_value(vars).map_err(|e| format!("{}", e))?;
+    match ctx.as_object_mut() {
+        Some(ctx) => {
+            ctx.insert("lang".to_string(), locale.code().into());
+            ctx.insert("title".to_string(), subject.clone().into());
+        }
+        None => return Err("Email template variables must be a map".to_string()),
+    }
+
+    with_environment(settings, |env| {
+        Ok(RenderedEmail {
+            html: render_localized(env, template, "html", locale, &ctx)?,
+            text: render_localized(env, template, "txt", locale, &ctx)?,
+            subject,
+        })
+    })
+}
+
+/// Рендерит служебную HTML-страницу из того же каталога шаблонов,
+/// например `dev/mailbox.html`.
+pub fn render_page<T: Serialize>(
+    name: &str,
+    ctx: T,
+    settings: &Settings,
+) -> Result<String, String> {
+    with_environment(settings, |env| {
+        env.get_template(name)
+            .and_then(|template| template.render(ctx))
+            .map_err(|e| format!("{}", e))
+    })
+}
+
+/// Общее окружение шаблонов, а с `email.reload_templates` — новое, читающее файлы заново.
+/// Пересоздаётся только окружение minijinja, настройки передаёт вызывающий.
+fn with_environment<R>(settings: &Settings, f: impl FnOnce(&Environment<'static>) -> R) -> R {
+    if settings.email.reload_templates {
+        f(&template_environment(settings))
+    } else {
+        f(TEMPLATES.get_or_init(|| template_environment(settings)))
+    }
+}
+
+/// Рендерит `emails/<имя>.<язык>.<расширение>`, а если такого шаблона нет —
+/// английский `emails/<имя>.<расширение>`.
+fn render_localized(
+    env: &Environment<'static>,
+    template: &str,
+    extension: &str,
+    locale: Locale,
+    ctx: &serde_json::Value,
+) -> Result<String, String> {
+    let default_name = format!("emails/{}.{}", template, extension);
+    let localized_name = format!("emails/{}.{}.{}", template, locale.code(), extension);
+    let localized = match locale {
+        Locale::En => env.get_template(&default_name),
+        _ => match env.get_template(&localized_name) {
+            Err(e) if e.kind() == ErrorKind::TemplateNotFound => env.get_template(&default_name),
+            result => result,
+        },
+    };
+
+    localized
+        .and_then(|template| template.render(ctx))
+        .map_err(|e| format!("{}", e))
+}
+
+/// Ключ письма в каталоге сообщений: `verification_email` → `verification`.
+fn template_key(template: &str) -> &str {
+    template.trim_end_matches("_email")
+}
+
+/// Данные для предпросмотра письма в `/dev/emails/{template}`.
+/// `None` — такого письма нет; новое письмо нужно добавить и сюда.
+pub fn preview_context(template: &str, settings: &Settings) -> Option<Value> {
+    let expiration = chrono::Local::now()
+        + chrono::Duration::try_minutes(settings.secret.token_expiration)
+            .map_or(chrono::Duration::zero(), |duration| duration);
+
+    match template {
+        "verification_email" => Some(minijinja::context! {
+            confirmation_link => format!(
+                "{}:{}/users/register/confirm/?token=preview-token",
+                settings.application.base_url, settings.application.port,
+            ),
+            domain => &settings.frontend_url,
+            expiration_time => settings.secret.token_expiration,
+            exact_time => expiration.format("%A %B %d, %Y at %r").to_string(),
+        }),
+        "account_exists_email" => Some(minijinja::context! {
+            first_name => "Ada",
+            login_link => format!("{}/auth/login", settings.frontend_url),
+            domain => &settings.frontend_url,
+        }),
+        _ => None,
+    }
+}
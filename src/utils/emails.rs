@@ -1,7 +1,12 @@
-use crate::settings::get_settings;
-use crate::utils::issue_confirmation_token_pasetors;
+use crate::i18n::Locale;
+use crate::metrics::METRICS;
+use crate::settings::{get_settings, EmailTransport};
+use crate::utils::email_templates::render_email;
+use crate::utils::outbox::{capture_email, CapturedEmail};
+use crate::utils::{issue_confirmation_token_pasetors, BackgroundTasks};
 use chrono::Duration;
 use lettre::AsyncTransport;
+use tokio::task::JoinHandle;
 use tracing::instrument;
 
 #[instrument(
@@ -30,20 +35,31 @@ pub async fn send_email(
 ) -> Result<(), String> {
     let settings = get_settings().expect("Failed to read settings.");
 
+    // Имя отправителя — из настроек `brand`
+    let from = format!(
+        "{} <{}>",
+        settings.brand.sender_name(),
+        sender_email.unwrap_or_else(|| settings.email.host_user.clone())
+    );
+
+    if settings.email.transport == EmailTransport::Memory {
+        capture_email(CapturedEmail {
+            id: uuid::Uuid::new_v4(),
+            from,
+            to: recipient_email,
+            to_name: [recipient_first_name, recipient_last_name].join(" "),
+            subject: subject.into(),
+            html: html_content.into(),
+            text: text_content.into(),
+            sent_at: chrono::Utc::now(),
+        });
+        tracing::event!(target: "backend", tracing::Level::INFO, "Email captured in the in-memory outbox");
+        METRICS.email_sent();
+        return Ok(());
+    }
+
     let email = lettre::Message::builder()
-        .from(
-            format!(
-                "{} <{}>",
-                "JohnWrites",
-                if sender_email.is_some() {
-                    sender_email.unwrap()
-                } else {
-                    settings.email.host_user.clone()
-                }
-            )
-            .parse()
-            .unwrap(),
-        )
+        .from(from.parse().unwrap())
         .to(format!(
             "{} <{}>",
             [recipient_first_name, recipient_last_name].join(" "),
@@ -83,40 +99,54 @@ pub async fn send_email(
     match mailer.send(email).await {
         Ok(_) => {
             tracing::event!(target: "backend", tracing::Level::INFO, "Email successfully sent!");
+            METRICS.email_sent();
             Ok(())
         }
         Err(e) => {
             tracing::event!(target: "backend", tracing::Level::ERROR, "Could not send email: {:#?}", e);
+            METRICS.email_failed();
             Err(format!("Could not send email: {:#?}", e))
         }
     }
 }
 
+/// Получатель письма: адрес, имя для обращения и язык письма.
+#[derive(Debug, Clone)]
+pub struct EmailRecipient {
+    pub user_id: uuid::Uuid,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub locale: Locale,
+}
+
+/// Выпускает токен подтверждения и отправляет письмо со ссылкой в фоновой задаче,
+/// которую приложение дожидается при остановке (см. `BackgroundTasks`).
+/// Возвращает её `JoinHandle`, чтобы вызывающий мог дождаться отправки (например, CLI).
+/// `template_name` — имя пары шаблонов в `templates/emails` без расширения.
 #[instrument(
 name = "Generic multipart e-mail sending function.",
-skip(redis_connection),
+skip(redis_connection, tasks),
 fields(
-recipient_user_id = %user_id,
-recipient_email = %recipient_email,
-recipient_first_name = %recipient_first_name,
-recipient_last_name = %recipient_last_name
+recipient_user_id = %recipient.user_id,
+recipient_email = %recipient.email,
+recipient_locale = %recipient.locale.code()
 )
 )]
 pub async fn send_multipart_email(
-    subject: String,
-    user_id: uuid::Uuid,
-    recipient_email: String,
-    recipient_first_name: String,
-    recipient_last_name: String,
+    recipient: EmailRecipient,
     template_name: &str,
+    next: Option<&str>,
     redis_connection: &mut deadpool_redis::redis::aio::Connection,
-) -> Result<(), String> {
+    tasks: &BackgroundTasks,
+) -> Result<JoinHandle<Result<(), String>>, String> {
     let settings = get_settings().expect("Unable to load settings (fn send_multipart_email).");
-    let title = subject.clone();
 
     let issued_token =
-        match issue_confirmation_token_pasetors(user_id, redis_connection, None).await {
-            Ok(t) => t,
+        match issue_confirmation_token_pasetors(recipient.user_id, redis_connection, None, next)
+            .await
+        {
+            Ok(token) => token,
             Err(e) => {
                 tracing::event!(target: "backend", tracing::Level::ERROR, "{}", e);
                 return Err(format!("{}", e));
@@ -130,12 +160,12 @@ pub async fn send_multipart_email(
                 settings.application.base_url, settings.application.port,
             )
         } else {
-            settings.application.base_url
+            settings.application.base_url.clone()
         }
     };
 
     let confirmation_link = {
-        if template_name == "password_reset_email.html" {
+        if template_name == "password_reset_email" {
             format!(
                 "{}/users/password/confirm/change_password?token={}",
                 web_address, issued_token,
@@ -153,31 +183,65 @@ pub async fn send_multipart_email(
         + Duration::try_minutes(settings.secret.token_expiration)
             .map_or(Duration::zero(), |duration| duration);
 
-    let template = crate::ENV.get_template(template_name).unwrap();
-    let ctx = minijinja::context! {
-        title => &title,
-        confirmation_link => &confirmation_link,
-        domain => &settings.frontend_url,
-        expiration_time => &settings.secret.token_expiration,
-        exact_time => &dt.format("%A %B %d, %Y at %r").to_string()
-    };
-    let html_text = template.render(ctx).unwrap();
-
-    let text = format!(
-        r#"
-        Tap the link below to confirm your email address.
-        {}
-        "#,
-        confirmation_link
-    );
-    tokio::spawn(send_email(
+    let email = render_email(
+        template_name,
+        recipient.locale,
+        minijinja::context! {
+            confirmation_link => &confirmation_link,
+            domain => &settings.frontend_url,
+            expiration_time => &settings.secret.token_expiration,
+            exact_time => &dt.format("%A %B %d, %Y at %r").to_string()
+        },
+        &settings,
+    )?;
+
+    Ok(tasks.spawn(send_email(
+        None,
+        recipient.email,
+        recipient.first_name,
+        recipient.last_name,
+        email.subject,
+        email.html,
+        email.text,
+    )))
+}
+
+/// Отправляет письмо-уведомление без токена подтверждения,
+/// например владельцу уже существующей учётной записи.
+#[instrument(
+name = "Notification e-mail sending function.",
+skip(tasks),
+fields(
+recipient_email = %recipient.email,
+recipient_locale = %recipient.locale.code()
+)
+)]
+pub async fn send_notification_email(
+    recipient: EmailRecipient,
+    template_name: &str,
+    tasks: &BackgroundTasks,
+) -> Result<(), String> {
+    let settings = get_settings().expect("Unable to load settings (fn send_notification_email).");
+
+    let email = render_email(
+        template_name,
+        recipient.locale,
+        minijinja::context! {
+            first_name => &recipient.first_name,
+            login_link => format!("{}/auth/login", settings.frontend_url),
+            domain => &settings.frontend_url,
+        },
+        &settings,
+    )?;
+
+    tasks.spawn(send_email(
         None,
-        recipient_email,
-        recipient_first_name,
-        recipient_last_name,
-        subject,
-        html_text,
-        text,
+        recipient.email,
+        recipient.first_name,
+        recipient.last_name,
+        email.subject,
+        email.html,
+        email.text,
     ));
     Ok(())
 }
@@ -1,10 +1,51 @@
+mod audit;
 mod auth;
+mod background;
+mod cors;
+mod csrf;
+mod email_normalization;
+mod email_templates;
 mod emails;
+mod outbox;
+mod session;
+mod validation;
 
-pub use auth::password::{hash, verify_password};
+pub use auth::password::{hash, needs_rehash, verify_dummy_password, verify_password};
 
-pub use emails::send_multipart_email;
+pub use auth::hash_schemes::HashScheme;
+
+pub use auth::password_policy::validate_password;
+
+pub use auth::breached_passwords::{
+    is_password_breached, parse_hash_line, BloomFilter, BreachedPasswordChecker,
+};
+
+pub use email_normalization::{
+    normalize_email, renormalize_emails, EmailRenormalization, NormalizedEmail,
+};
+
+pub use emails::{send_multipart_email, send_notification_email, EmailRecipient};
+
+pub use email_templates::{preview_context, render_email, render_page, RenderedEmail};
+
+pub use outbox::{
+    captured_emails, clear_captured_emails, email_links, find_captured_email, CapturedEmail,
+};
 
 pub use auth::tokens::issue_confirmation_token_pasetors;
 
 pub use auth::tokens::verify_confirmation_token_pasetor;
+
+pub use audit::{client_ip, record_auth_event, TrustedProxies};
+
+pub use background::{spawn_blocking_in_span, BackgroundTasks};
+
+pub use cors::{is_allowed_origin, is_allowed_redirect, origin_of, resolve_frontend_url};
+
+pub use csrf::{session_csrf_token, verify_csrf_token, CSRF_HEADER};
+
+pub use session::{session_user_id, session_user_is_admin};
+
+pub use validation::{
+    json_config, normalize_text, query_config, Normalize, ValidatedJson, ValidatedQuery,
+};
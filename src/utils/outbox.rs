@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Сколько последних писем хранится в памяти; более старые вытесняются.
+const OUTBOX_CAPACITY: usize = 1000;
+
+static OUTBOX: Lazy<Mutex<Vec<CapturedEmail>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Письмо, перехваченное транспортом `memory` вместо отправки по SMTP.
+#[derive(Serialize, Debug, Clone)]
+pub struct CapturedEmail {
+    pub id: Uuid,
+    pub from: String,
+    pub to: String,
+    pub to_name: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+    pub sent_at: DateTime<Utc>,
+}
+
+pub fn capture_email(email: CapturedEmail) {
+    let mut outbox = OUTBOX.lock().expect("Email outbox lock is poisoned.");
+    if outbox.len() >= OUTBOX_CAPACITY {
+        outbox.remove(0);
+    }
+    outbox.push(email);
+}
+
+/// Копия всех перехваченных писем, от старых к новым.
+pub fn captured_emails() -> Vec<CapturedEmail> {
+    OUTBOX
+        .lock()
+        .expect("Email outbox lock is poisoned.")
+        .clone()
+}
+
+/// Перехваченное письмо по идентификатору.
+pub fn find_captured_email(id: Uuid) -> Option<CapturedEmail> {
+    OUTBOX
+        .lock()
+        .expect("Email outbox lock is poisoned.")
+        .iter()
+        .find(|email| email.id == id)
+        .cloned()
+}
+
+/// Очищает ящик, например между ручными проверками в `/dev/mailbox`.
+pub fn clear_captured_emails() {
+    OUTBOX
+        .lock()
+        .expect("Email outbox lock is poisoned.")
+        .clear();
+}
+
+/// Ссылки `http(s)://` из текстовой части письма в порядке появления.
+pub fn email_links(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .map(str::to_string)
+        .collect()
+}
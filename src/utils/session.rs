@@ -0,0 +1,40 @@
+use crate::types::USER_ID_KEY;
+use actix_session::Session;
+use sqlx::{PgPool, Row};
+use tracing::instrument;
+use uuid::Uuid;
+
+#[instrument(name = "Get user_id from session.", skip(session))]
+pub async fn session_user_id(session: &Session) -> Result<Uuid, String> {
+    match session.get(USER_ID_KEY) {
+        Ok(user_id) => match user_id {
+            None => Err("You are not authenticated".to_string()),
+            Some(id) => Ok(id),
+        },
+        Err(e) => Err(format!("{}", e)),
+    }
+}
+
+/// Проверяет, что в сессии авторизован сотрудник (`is_staff`) или суперпользователь.
+/// Права читаются из БД при каждой проверке, а не из сессии: отозванные права
+/// или деактивация действуют сразу, не дожидаясь истечения сессии.
+#[instrument(name = "Check staff rights of session user.", skip(session, pool))]
+pub async fn session_user_is_admin(session: &Session, pool: &PgPool) -> Result<Uuid, String> {
+    let user_id = session_user_id(session).await?;
+
+    let is_admin = sqlx::query(
+        "SELECT COALESCE(is_staff OR is_superuser, FALSE) AS is_admin FROM users \
+        WHERE id = $1 AND is_active",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("{}", e))?
+    .is_some_and(|row| row.get::<bool, _>("is_admin"));
+
+    if is_admin {
+        Ok(user_id)
+    } else {
+        Err("You do not have permission to perform this action".to_string())
+    }
+}
@@ -0,0 +1,258 @@
+use crate::i18n::{request_locale, t, t_with, Locale};
+use crate::types::{ErrorResponse, FieldError, ValidationErrorResponse};
+use actix_web::dev::Payload;
+use actix_web::error::{InternalError, JsonPayloadError, QueryPayloadError};
+use actix_web::web::{Json, JsonConfig, Query, QueryConfig};
+use actix_web::{Error, FromRequest, HttpRequest, HttpResponse};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+use std::future::{ready, Future, Ready};
+use std::ops::Deref;
+use std::pin::Pin;
+use unicode_normalization::UnicodeNormalization;
+use validator::{Validate, ValidationErrors};
+
+/// Приведение данных запроса к каноническому виду перед проверкой:
+/// обрезка пробелов, нормализация Unicode и т.п. Пароли не изменяются.
+pub trait Normalize {
+    fn normalize(&mut self);
+}
+
+/// Обрезает пробелы по краям и приводит строку к форме Unicode NFC.
+pub fn normalize_text(value: &mut String) {
+    *value = value.trim().nfc().collect();
+}
+
+/// Тело запроса в JSON, прошедшее нормализацию и декларативную проверку (`validator`).
+/// При ошибке клиент получает `ValidationErrorResponse` со списком ошибок по каждому полю.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate + Normalize + 'static,
+{
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = Json::<T>::from_request(req, payload);
+        let locale = request_locale(req);
+        Box::pin(async move {
+            let mut value = json.await?.into_inner();
+            value.normalize();
+            value
+                .validate()
+                .map_err(|errors| validation_error(errors, locale))?;
+            Ok(ValidatedJson(value))
+        })
+    }
+}
+
+/// Параметры строки запроса, прошедшие нормализацию и проверку, аналог `ValidatedJson`.
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T> ValidatedQuery<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> FromRequest for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate + Normalize + 'static,
+{
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            Query::<T>::from_query(req.query_string())
+                .map_err(|e| query_error_handler(e, req))
+                .and_then(|query| {
+                    let mut value = query.into_inner();
+                    value.normalize();
+                    value
+                        .validate()
+                        .map_err(|errors| validation_error(errors, request_locale(req)))?;
+                    Ok(ValidatedQuery(value))
+                }),
+        )
+    }
+}
+
+/// Настройки `Json`-экстрактора: ошибки разбора тела отдаются в формате `ValidationErrorResponse`,
+/// а не текстом по умолчанию от actix.
+pub fn json_config() -> JsonConfig {
+    JsonConfig::default().error_handler(json_error_handler)
+}
+
+/// Настройки `Query`-экстрактора, аналог `json_config`.
+pub fn query_config() -> QueryConfig {
+    QueryConfig::default().error_handler(query_error_handler)
+}
+
+fn json_error_handler(err: JsonPayloadError, req: &HttpRequest) -> Error {
+    tracing::event!(target: "backend", tracing::Level::INFO, "Invalid JSON payload: {}", err);
+    let locale = request_locale(req);
+
+    let response = match &err {
+        JsonPayloadError::ContentType => {
+            HttpResponse::UnsupportedMediaType().json(single_error_response(
+                "body",
+                "unsupported_content_type",
+                &t(locale, "error.unsupported_content_type"),
+                locale,
+            ))
+        }
+        JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+            HttpResponse::PayloadTooLarge().json(single_error_response(
+                "body",
+                "payload_too_large",
+                &t(locale, "error.payload_too_large"),
+                locale,
+            ))
+        }
+        JsonPayloadError::Deserialize(e) => {
+            HttpResponse::BadRequest().json(deserialize_error_response(&e.to_string(), locale))
+        }
+        _ => HttpResponse::BadRequest().json(single_error_response(
+            "body",
+            "invalid_body",
+            &err.to_string(),
+            locale,
+        )),
+    };
+
+    InternalError::from_response(err, response).into()
+}
+
+fn query_error_handler(err: QueryPayloadError, req: &HttpRequest) -> Error {
+    tracing::event!(target: "backend", tracing::Level::INFO, "Invalid query string: {}", err);
+    let locale = request_locale(req);
+
+    let response = match &err {
+        QueryPayloadError::Deserialize(e) => {
+            HttpResponse::BadRequest().json(deserialize_error_response(&e.to_string(), locale))
+        }
+        _ => HttpResponse::BadRequest().json(single_error_response(
+            "query",
+            "invalid_query",
+            &err.to_string(),
+            locale,
+        )),
+    };
+
+    InternalError::from_response(err, response).into()
+}
+
+/// serde не отдаёт структурированных ошибок, поэтому имя поля достаётся из текста,
+/// например "missing field `email`" или "invalid type: ... for field `page`".
+/// Текст serde не переводится: для отсутствующего поля подставляется сообщение из каталога.
+fn deserialize_error_response(message: &str, locale: Locale) -> ValidationErrorResponse {
+    let field = message
+        .split('`')
+        .nth(1)
+        .filter(|_| message.contains("field `"))
+        .unwrap_or("body");
+    if message.starts_with("missing field") {
+        single_error_response(field, "required", &t(locale, "field.required"), locale)
+    } else {
+        single_error_response(field, "invalid_value", message, locale)
+    }
+}
+
+fn single_error_response(
+    field: &str,
+    code: &str,
+    message: &str,
+    locale: Locale,
+) -> ValidationErrorResponse {
+    ValidationErrorResponse {
+        base: ErrorResponse {
+            error: t(locale, "error.invalid_request"),
+        },
+        fields: BTreeMap::from([(
+            field.to_string(),
+            vec![FieldError {
+                code: code.to_string(),
+                message: message.to_string(),
+            }],
+        )]),
+    }
+}
+
+fn validation_error(errors: ValidationErrors, locale: Locale) -> Error {
+    tracing::event!(target: "backend", tracing::Level::INFO, "Request validation failed: {}", errors);
+
+    let fields = errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, field_errors)| {
+            let field_errors = field_errors
+                .iter()
+                .map(|error| FieldError {
+                    code: error.code.to_string(),
+                    message: error
+                        .message
+                        .as_ref()
+                        .map(|message| message.to_string())
+                        .unwrap_or_else(|| default_message(error, locale)),
+                })
+                .collect();
+            (field.to_string(), field_errors)
+        })
+        .collect();
+
+    let response = HttpResponse::BadRequest().json(ValidationErrorResponse {
+        base: ErrorResponse {
+            error: t(locale, "error.invalid_request"),
+        },
+        fields,
+    });
+    InternalError::from_response(errors, response).into()
+}
+
+/// Сообщение для стандартных правил `validator`, если в атрибуте не задано своё.
+fn default_message(error: &validator::ValidationError, locale: Locale) -> String {
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+    let bounded = |prefix: &str| match (param("min"), param("max")) {
+        (Some(min), Some(max)) => t_with(
+            locale,
+            &format!("{}_between", prefix),
+            &[("min", &min), ("max", &max)],
+        ),
+        (Some(min), None) => t_with(locale, &format!("{}_min", prefix), &[("min", &min)]),
+        (None, Some(max)) => t_with(locale, &format!("{}_max", prefix), &[("max", &max)]),
+        (None, None) => t(locale, prefix),
+    };
+
+    match error.code.as_ref() {
+        "email" => t(locale, "field.email"),
+        "required" => t(locale, "field.required"),
+        "length" => bounded("field.length"),
+        "range" => bounded("field.range"),
+        _ => t(locale, "field.invalid"),
+    }
+}
@@ -0,0 +1,48 @@
+use crate::helpers::{spawn_app, TestApp};
+use reqwest::header::{ACCESS_CONTROL_ALLOW_ORIGIN, ORIGIN};
+use reqwest::{Method, Response};
+use sqlx::PgPool;
+
+async fn preflight(app: &TestApp, origin: &str) -> Response {
+    app.client
+        .request(Method::OPTIONS, format!("{}/users/login/", app.address))
+        .header(ORIGIN, origin)
+        .header("Access-Control-Request-Method", "POST")
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[sqlx::test]
+async fn configured_origins_pass_preflight(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    for origin in [
+        "https://localhost:3000",
+        "https://admin.localhost:3001",
+        "https://pr-42.preview.localhost",
+    ] {
+        let response = preflight(&app, origin).await;
+        assert_eq!(response.status().as_u16(), 200, "{}", origin);
+        assert_eq!(response.headers()[ACCESS_CONTROL_ALLOW_ORIGIN], origin);
+    }
+}
+
+#[sqlx::test]
+async fn unknown_origins_are_rejected(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    for origin in [
+        "https://evil.example.com",
+        "https://preview.localhost",
+        "https://pr-42.preview.localhost.evil.com",
+        "http://pr-42.preview.localhost",
+    ] {
+        let response = preflight(&app, origin).await;
+        assert!(
+            !response.headers().contains_key(ACCESS_CONTROL_ALLOW_ORIGIN),
+            "{}",
+            origin
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use crate::helpers::{spawn_app, unique_email, wait_for_email, TestApp, PASSWORD};
+use reqwest::header::{CONTENT_SECURITY_POLICY, CONTENT_TYPE};
+use reqwest::Response;
+use sqlx::PgPool;
+
+async fn get_email_preview(app: &TestApp, template: &str, query: &[(&str, &str)]) -> Response {
+    app.client
+        .get(format!("{}/dev/emails/{}", app.address, template))
+        .query(query)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[sqlx::test]
+async fn email_preview_renders_html_with_the_layout(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = get_email_preview(&app, "verification_email", &[]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.headers()[CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    assert!(response.headers()[CONTENT_SECURITY_POLICY]
+        .to_str()
+        .unwrap()
+        .contains("style-src 'unsafe-inline'"));
+    let body = response.text().await.unwrap();
+    assert!(body.contains("get you verified</title>"));
+    assert!(body.contains("token=preview-token"));
+    assert!(body.contains("You received this email because"));
+}
+
+#[sqlx::test]
+async fn email_preview_renders_localized_text(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = get_email_preview(
+        &app,
+        "account_exists_email",
+        &[("locale", "ru"), ("format", "text")],
+    )
+    .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let body = response.text().await.unwrap();
+    assert!(body.starts_with("Subject: RustAuth — у вас уже есть учётная запись"));
+    assert!(body.contains("Здравствуйте, Ada!"));
+    assert!(body.contains("https://localhost:3000/auth/login"));
+}
+
+#[sqlx::test]
+async fn unknown_email_template_is_not_found(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = get_email_preview(&app, "base", &[]).await;
+    assert_eq!(response.status().as_u16(), 404);
+}
+
+#[sqlx::test]
+async fn registration_completes_through_the_mailbox_api(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let email = app.register_new_user().await;
+    wait_for_email(&email).await;
+
+    let messages: serde_json::Value = app
+        .client
+        .get(format!("{}/dev/mailbox/api/messages", app.address))
+        .query(&[("to", email.to_uppercase())])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .json()
+        .await
+        .unwrap();
+    let messages = messages.as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    let link = messages[0]["links"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|link| link.as_str())
+        .find(|link| link.contains("/users/register/confirm/?token="))
+        .expect("No confirmation link in the mailbox message.");
+    // Ссылка ведёт на `application.port` из настроек, а тестовый сервер слушает случайный порт
+    let query = link.split_once('?').unwrap().1;
+
+    let response = app
+        .client
+        .get(format!("{}/users/register/confirm/?{}", app.address, query))
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn mailbox_pages_show_messages_with_clickable_links(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let email = unique_email();
+    app.post_register(&serde_json::json!({
+        "email": email,
+        "password": PASSWORD,
+        "first_name": "Mail",
+        "last_name": "Box",
+    }))
+    .await;
+    let sent = wait_for_email(&email).await;
+
+    let page = app
+        .client
+        .get(format!("{}/dev/mailbox", app.address))
+        .query(&[("to", &email)])
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .text()
+        .await
+        .unwrap();
+    assert!(page.contains(&format!("/dev/mailbox/{}", sent.id)));
+
+    let response = app
+        .client
+        .get(format!("{}/dev/mailbox/{}", app.address, sent.id))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    let page = response.text().await.unwrap();
+    assert!(page.contains("<a href=\"http"));
+    // Ссылки берутся из текста письма и экранируются шаблоном (`/` → `&#x2f;`),
+    // браузер раскодирует их обратно.
+    assert!(page
+        .replace("&#x2f;", "/")
+        .contains("/users/register/confirm/?token="));
+    assert!(page.contains(&format!("/dev/mailbox/{}/html", sent.id)));
+
+    let response = app
+        .client
+        .get(format!(
+            "{}/dev/mailbox/{}",
+            app.address,
+            uuid::Uuid::new_v4()
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -0,0 +1,100 @@
+//! Минимальный Redis внутри процесса теста: понимает только команды,
+//! которые использует приложение (`PING`, `SET`, `GET`, `DEL`, `EXPIRE`).
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+type Store = Arc<Mutex<HashMap<String, (String, Option<Instant>)>>>;
+
+/// Запускает сервер на случайном порту и возвращает его URI вида `redis://127.0.0.1:PORT`.
+pub async fn spawn_fake_redis() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind fake redis.");
+    let uri = format!("redis://{}", listener.local_addr().unwrap());
+    let store: Store = Arc::default();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, store.clone()));
+        }
+    });
+
+    uri
+}
+
+async fn handle_connection(stream: TcpStream, store: Store) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    while let Some(command) = read_command(&mut reader).await {
+        let reply = execute(&command, &store);
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Читает команду в формате RESP: массив bulk-строк.
+async fn read_command<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<Vec<String>> {
+    let count: usize = read_line(reader).await?.strip_prefix('*')?.parse().ok()?;
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        let len: usize = read_line(reader).await?.strip_prefix('$')?.parse().ok()?;
+        let mut buf = vec![0; len + 2];
+        reader.read_exact(&mut buf).await.ok()?;
+        buf.truncate(len);
+        args.push(String::from_utf8(buf).ok()?);
+    }
+    Some(args)
+}
+
+async fn read_line<R: AsyncBufReadExt + Unpin>(reader: &mut R) -> Option<String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line).await {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end().to_string()),
+    }
+}
+
+fn execute(command: &[String], store: &Store) -> String {
+    let Some((name, arguments)) = command.split_first() else {
+        return "-ERR empty command\r\n".to_string();
+    };
+    let mut store = store.lock().unwrap();
+    store.retain(|_, (_, expires_at)| expires_at.is_none_or(|at| at > Instant::now()));
+
+    match (name.to_uppercase().as_str(), arguments) {
+        ("PING", []) => "+PONG\r\n".to_string(),
+        ("PING", [message]) => bulk(message),
+        ("SET", [key, value]) => {
+            store.insert(key.clone(), (value.clone(), None));
+            "+OK\r\n".to_string()
+        }
+        ("GET", [key]) => match store.get(key) {
+            Some((value, _)) => bulk(value),
+            None => "$-1\r\n".to_string(),
+        },
+        ("DEL", keys) => {
+            let removed = keys
+                .iter()
+                .filter(|key| store.remove(*key).is_some())
+                .count();
+            format!(":{}\r\n", removed)
+        }
+        ("EXPIRE", [key, seconds]) => match (store.get_mut(key), seconds.parse::<u64>()) {
+            (Some((_, expires_at)), Ok(seconds)) => {
+                *expires_at = Some(Instant::now() + Duration::from_secs(seconds));
+                ":1\r\n".to_string()
+            }
+            _ => ":0\r\n".to_string(),
+        },
+        (name, _) => format!("-ERR unsupported command '{}'\r\n", name),
+    }
+}
+
+fn bulk(value: &str) -> String {
+    format!("${}\r\n{}\r\n", value.len(), value)
+}
@@ -0,0 +1,78 @@
+use crate::helpers::spawn_app;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn liveness_probe_returns_200(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn readiness_probe_reports_every_dependency(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/ready", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["status"], "ok");
+    assert_eq!(report["checks"]["database"]["status"], "ok");
+    assert_eq!(report["checks"]["redis"]["status"], "ok");
+    assert_eq!(report["checks"]["migrations"]["status"], "ok");
+    assert_eq!(report["checks"]["smtp"]["status"], "skipped");
+}
+
+#[sqlx::test]
+async fn readiness_probe_fails_when_migrations_are_pending(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    sqlx::query(
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .client
+        .get(format!("{}/health/ready", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 503);
+
+    let report: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(report["checks"]["migrations"]["status"], "error");
+}
+
+#[sqlx::test]
+async fn responses_carry_security_headers(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let headers = response.headers();
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert_eq!(headers["referrer-policy"], "no-referrer");
+    assert!(headers.contains_key("content-security-policy"));
+    assert!(headers.contains_key("permissions-policy"));
+    // HSTS только в staging и production
+    assert!(!headers.contains_key("strict-transport-security"));
+}
@@ -0,0 +1,200 @@
+use crate::fake_redis::spawn_fake_redis;
+use backend::settings::{get_settings, Settings};
+use backend::startup::{Application, ShutdownHandle};
+use backend::telemetry::{get_subscriber, init_subscriber};
+use backend::utils::{captured_emails, email_links, CapturedEmail};
+use once_cell::sync::Lazy;
+use reqwest::header::{COOKIE, SET_COOKIE};
+use reqwest::Response;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+pub const PASSWORD: &str = "correct-Horse-battery-42";
+
+/// Выполняется один раз на процесс: включает среду `testing` (письма складываются в память,
+/// а не уходят по SMTP). Часть кода читает настройки сама через `get_settings`, поэтому
+/// среда задаётся переменной окружения. Логи выводятся только при `TEST_LOG=1`.
+static INIT: Lazy<()> = Lazy::new(|| {
+    std::env::set_var("APP_ENVIRONMENT", "testing");
+
+    if std::env::var("TEST_LOG").is_ok() {
+        init_subscriber(get_subscriber(true, None));
+    }
+});
+
+pub fn init() {
+    Lazy::force(&INIT);
+}
+
+pub struct TestApp {
+    pub address: String,
+    pub pool: PgPool,
+    pub client: reqwest::Client,
+    pub shutdown: ShutdownHandle,
+    pub server: JoinHandle<std::io::Result<()>>,
+}
+
+/// Поднимает приложение на случайном порту поверх базы, созданной `#[sqlx::test]`,
+/// и отдельного Redis внутри процесса.
+pub async fn spawn_app(pool: PgPool) -> TestApp {
+    spawn_app_with(pool, |_| {}).await
+}
+
+/// Как `spawn_app`, но с изменёнными настройками приложения. Действует на обработчики,
+/// которые получают настройки через `Data<Settings>`.
+pub async fn spawn_app_with(pool: PgPool, configure: impl FnOnce(&mut Settings)) -> TestApp {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.redis.uri = spawn_fake_redis().await;
+    configure(&mut settings);
+
+    let application = Application::build(settings, Some(pool.clone()))
+        .await
+        .expect("Failed to build application.");
+    let address = format!("http://127.0.0.1:{}", application.port());
+    let shutdown = application.shutdown_handle();
+    let server = tokio::spawn(application.run_until_stopped());
+
+    // Редиректы проверяются в тестах, а не выполняются клиентом.
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    TestApp {
+        address,
+        pool,
+        client,
+        shutdown,
+        server,
+    }
+}
+
+impl TestApp {
+    pub async fn post_register(&self, body: &serde_json::Value) -> Response {
+        self.client
+            .post(format!("{}/users/register/", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_confirm(&self, token: &str) -> Response {
+        self.client
+            .get(format!("{}/users/register/confirm/", self.address))
+            .query(&[("token", token)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_login(&self, body: &serde_json::Value) -> Response {
+        self.client
+            .post(format!("{}/users/login/", self.address))
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_logout(
+        &self,
+        session_cookie: Option<&str>,
+        csrf_token: Option<&str>,
+    ) -> Response {
+        let mut request = self.client.post(format!("{}/users/logout/", self.address));
+        if let Some(cookie) = session_cookie {
+            request = request.header(COOKIE, cookie);
+        }
+        if let Some(token) = csrf_token {
+            request = request.header("X-CSRF-Token", token);
+        }
+        request.send().await.expect("Failed to execute request.")
+    }
+
+    /// CSRF-токен сессии и обновлённая cookie: токен хранится в самой сессии.
+    pub async fn get_csrf_token(&self, cookie: &str) -> (String, String) {
+        let response = self
+            .client
+            .get(format!("{}/users/csrf-token/", self.address))
+            .header(COOKIE, cookie)
+            .send()
+            .await
+            .expect("Failed to execute request.");
+        assert_eq!(response.status().as_u16(), 200);
+        let cookie = session_cookie(&response).unwrap_or_else(|| cookie.to_string());
+        let body: serde_json::Value = response.json().await.unwrap();
+        (body["csrf_token"].as_str().unwrap().to_string(), cookie)
+    }
+
+    /// Регистрирует пользователя с уникальным адресом и возвращает этот адрес.
+    pub async fn register_new_user(&self) -> String {
+        let email = unique_email();
+        let response = self
+            .post_register(&serde_json::json!({
+                "email": email,
+                "password": PASSWORD,
+                "first_name": "Test",
+                "last_name": "User",
+            }))
+            .await;
+        assert_eq!(response.status().as_u16(), 200);
+        email
+    }
+}
+
+pub fn unique_email() -> String {
+    format!("user-{}@example.com", uuid::Uuid::new_v4())
+}
+
+/// Ждёт письмо для адресата: письма отправляются в фоновой задаче.
+pub async fn wait_for_email(recipient: &str) -> CapturedEmail {
+    wait_for_email_where(recipient, |_| true).await
+}
+
+/// Ждёт письмо для адресата, подходящее под условие, например по теме.
+pub async fn wait_for_email_where(
+    recipient: &str,
+    matches: impl Fn(&CapturedEmail) -> bool,
+) -> CapturedEmail {
+    for _ in 0..50 {
+        if let Some(email) = captured_emails()
+            .into_iter()
+            .rev()
+            .find(|email| email.to == recipient && matches(email))
+        {
+            return email;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("No email was sent to {}", recipient);
+}
+
+/// Ссылка подтверждения из текстовой части письма.
+pub fn confirmation_link(email: &CapturedEmail) -> String {
+    email_links(&email.text)
+        .into_iter()
+        .find(|link| link.contains("/users/register/confirm/?token="))
+        .expect("No confirmation link in the email.")
+}
+
+pub fn confirmation_token(email: &CapturedEmail) -> String {
+    let link = confirmation_link(email);
+    link.split_once("token=").unwrap().1.to_string()
+}
+
+/// Cookie сессии из ответа в виде `id=...` для заголовка `Cookie`.
+/// Cookie выставляется с `Secure`, поэтому хранилище cookie клиента по http её не вернёт.
+pub fn session_cookie(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get_all(SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|value| value.split(';').next())
+        .find(|pair| pair.starts_with("id="))
+        .map(str::to_string)
+}
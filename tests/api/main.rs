@@ -0,0 +1,12 @@
+mod cors;
+mod dev;
+mod fake_redis;
+mod health;
+mod helpers;
+mod metrics;
+mod openapi;
+mod request_id;
+mod settings;
+mod shutdown;
+mod tls;
+mod users;
@@ -0,0 +1,37 @@
+use crate::helpers::spawn_app;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn metrics_endpoint_exposes_auth_counters(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    app.register_new_user().await;
+
+    let response = app
+        .client
+        .get(format!("{}/metrics", app.address))
+        .bearer_auth("testing-metrics-token")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let body = response.text().await.unwrap();
+    assert!(body.contains("auth_registrations_total{outcome=\"success\"}"));
+    assert!(body.contains("http_request_duration_seconds_bucket"));
+    assert!(body.contains("route=\"/users/register/\""));
+    assert!(body.contains("db_pool_connections{state=\"total\"}"));
+}
+
+#[sqlx::test]
+async fn metrics_require_the_bearer_token(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/metrics", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,28 @@
+use backend::routes::api_doc;
+use std::path::PathBuf;
+
+/// Сверяет документ OpenAPI со снимком `tests/snapshots/openapi.json`,
+/// чтобы изменения контракта API были видны в ревью.
+/// Снимок записывается только при `UPDATE_OPENAPI_SNAPSHOT=1`; без снимка тест падает.
+#[test]
+fn openapi_document_matches_snapshot() {
+    let snapshot = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/openapi.json");
+    let document = api_doc()
+        .to_pretty_json()
+        .expect("Failed to serialize OpenAPI document.");
+
+    if std::env::var("UPDATE_OPENAPI_SNAPSHOT").is_ok() {
+        std::fs::create_dir_all(snapshot.parent().unwrap()).unwrap();
+        std::fs::write(&snapshot, format!("{}\n", document)).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&snapshot).expect(
+        "OpenAPI snapshot is missing. Run `UPDATE_OPENAPI_SNAPSHOT=1 cargo test openapi` and commit it.",
+    );
+    assert_eq!(
+        expected.trim_end(),
+        document,
+        "OpenAPI document changed. Run `UPDATE_OPENAPI_SNAPSHOT=1 cargo test openapi` and commit the snapshot."
+    );
+}
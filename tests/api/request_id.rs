@@ -0,0 +1,88 @@
+use crate::helpers::spawn_app;
+use sqlx::PgPool;
+
+#[sqlx::test]
+async fn incoming_request_id_is_echoed_back(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .header("X-Request-Id", "edge-7f3a91")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["x-request-id"], "edge-7f3a91");
+}
+
+#[sqlx::test]
+async fn request_id_is_generated_when_missing_or_invalid(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let generated = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .header("X-Request-Id", "a".repeat(200))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let replaced = response.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(replaced).is_ok());
+}
+
+#[sqlx::test]
+async fn error_responses_carry_the_request_id(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .post(format!("{}/users/register/", app.address))
+        .header("X-Request-Id", "support-ticket-42")
+        .json(&serde_json::json!({ "email": "not-an-email" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(response.headers()["x-request-id"], "support-ticket-42");
+
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], "support-ticket-42");
+    assert!(body["error"].is_string());
+}
+
+#[sqlx::test]
+async fn routed_requests_pass_through_every_middleware(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    // Маршрут с параметром пути: маршрутизатор изменяет `match_info` запроса.
+    let response = app
+        .client
+        .get(format!(
+            "{}/dev/mailbox/api/messages/{}",
+            app.address,
+            uuid::Uuid::new_v4()
+        ))
+        .header("X-Request-Id", "routed-1")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(response.headers()["x-request-id"], "routed-1");
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["request_id"], "routed-1");
+
+    // Ошибка из middleware (CSRF) тоже получает идентификатор.
+    let response = app.post_logout(Some("id=forged"), None).await;
+    assert!(response.status().is_client_error());
+    assert!(response.headers().contains_key("x-request-id"));
+}
@@ -0,0 +1,83 @@
+use crate::helpers::init;
+use backend::settings::get_settings;
+
+#[test]
+fn testing_settings_are_valid() {
+    init();
+
+    let settings = get_settings().expect("Failed to read settings.");
+    assert!(settings.validate().is_ok());
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.secret.secret_key = "too-short".to_string();
+    settings.secret.hmac_secret = String::new();
+    settings.secret.token_expiration = 0;
+
+    let errors = settings.validate().unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("exactly 32 bytes"));
+    assert!(errors[1].contains("hmac_secret is empty"));
+    assert!(errors[2].contains("token_expiration"));
+}
+
+#[test]
+fn enabled_tls_requires_certificate_paths() {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.application.tls.enabled = true;
+    settings.application.tls.cert_path = String::new();
+    settings.application.tls.redirect_http_port = Some(settings.application.port);
+
+    let errors = settings.validate().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("cert_path and key_path"));
+    assert!(errors[1].contains("redirect_http_port"));
+}
+
+#[test]
+fn host_prefixed_session_cookie_must_be_secure_and_host_only() {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.session.cookie_name = "__Host-id".to_string();
+    settings.session.cookie_domain = Some("example.com".to_string());
+
+    let errors = settings.validate().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("__Host- prefix"));
+}
+
+#[test]
+fn enabled_otlp_exporter_requires_endpoint_and_sampling_ratio() {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.otlp.enabled = true;
+    settings.otlp.endpoint = String::new();
+    settings.otlp.sampling_ratio = 1.5;
+
+    let errors = settings.validate().unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].contains("otlp.endpoint"));
+    assert!(errors[1].contains("otlp.sampling_ratio"));
+}
+
+#[test]
+fn public_metrics_require_a_bearer_token() {
+    init();
+
+    let mut settings = get_settings().expect("Failed to read settings.");
+    settings.metrics.enabled = true;
+    settings.metrics.bind_address = None;
+    settings.metrics.bearer_token = None;
+
+    let errors = settings.validate().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("metrics.bearer_token"));
+}
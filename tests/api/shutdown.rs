@@ -0,0 +1,27 @@
+use crate::helpers::{spawn_app, wait_for_email};
+use sqlx::PgPool;
+use std::time::Duration;
+
+#[sqlx::test]
+async fn shutdown_handle_stops_the_server_after_sending_emails(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+
+    app.shutdown.shutdown();
+    tokio::time::timeout(Duration::from_secs(10), app.server)
+        .await
+        .expect("Server did not stop in time.")
+        .expect("Server task panicked.")
+        .expect("Server stopped with an error.");
+
+    // Письмо, запущенное до остановки, отправлено.
+    wait_for_email(&email).await;
+
+    let response = app
+        .client
+        .get(format!("{}/health/live", app.address))
+        .send()
+        .await;
+    assert!(response.is_err());
+}
@@ -0,0 +1,210 @@
+use crate::helpers::{spawn_app_with, TestApp};
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, MsbOption};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::x509::extension::{ExtendedKeyUsage, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use reqwest::header::LOCATION;
+use reqwest::Version;
+use sqlx::PgPool;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Самоподписанный сертификат для `127.0.0.1`, созданный на время теста.
+struct TestCertificate {
+    cert_pem: Vec<u8>,
+    key_pem: Vec<u8>,
+}
+
+impl TestCertificate {
+    fn generate() -> Self {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "localhost")
+            .unwrap();
+        let name = name.build();
+
+        let mut serial = BigNum::new().unwrap();
+        serial.rand(64, MsbOption::MAYBE_ZERO, false).unwrap();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder
+            .set_serial_number(&serial.to_asn1_integer().unwrap())
+            .unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        let subject_alt_name = SubjectAlternativeName::new()
+            .ip("127.0.0.1")
+            .dns("localhost")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(subject_alt_name).unwrap();
+        builder
+            .append_extension(ExtendedKeyUsage::new().server_auth().build().unwrap())
+            .unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+
+        Self {
+            cert_pem: builder.build().to_pem().unwrap(),
+            key_pem: key.private_key_to_pem_pkcs8().unwrap(),
+        }
+    }
+
+    /// Клиент, который доверяет только этому сертификату.
+    fn client(&self) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(reqwest::Certificate::from_pem(&self.cert_pem).unwrap())
+            .redirect(reqwest::redirect::Policy::none())
+    }
+}
+
+/// Каталог с `cert.pem` и `key.pem`, удаляется вместе со значением.
+struct CertificateFiles(PathBuf);
+
+impl CertificateFiles {
+    fn new(certificate: &TestCertificate) -> Self {
+        let directory = std::env::temp_dir().join(format!("tls-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&directory).unwrap();
+        let files = Self(directory);
+        files.replace(certificate);
+        files
+    }
+
+    fn replace(&self, certificate: &TestCertificate) {
+        std::fs::write(self.cert_path(), &certificate.cert_pem).unwrap();
+        std::fs::write(self.key_path(), &certificate.key_pem).unwrap();
+    }
+
+    fn cert_path(&self) -> PathBuf {
+        self.0.join("cert.pem")
+    }
+
+    fn key_path(&self) -> PathBuf {
+        self.0.join("key.pem")
+    }
+}
+
+impl Drop for CertificateFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+async fn spawn_tls_app(
+    pool: PgPool,
+    files: &CertificateFiles,
+    redirect_http_port: Option<u16>,
+) -> (TestApp, String) {
+    let app = spawn_app_with(pool, |settings| {
+        let tls = &mut settings.application.tls;
+        tls.enabled = true;
+        tls.cert_path = files.cert_path().to_string_lossy().into_owned();
+        tls.key_path = files.key_path().to_string_lossy().into_owned();
+        tls.reload_interval_seconds = 1;
+        tls.redirect_http_port = redirect_http_port;
+    })
+    .await;
+    let https_address = app.address.replacen("http://", "https://", 1);
+    (app, https_address)
+}
+
+#[sqlx::test]
+async fn https_requests_negotiate_http2_or_http1_via_alpn(pool: PgPool) {
+    let certificate = TestCertificate::generate();
+    let files = CertificateFiles::new(&certificate);
+    let (_app, address) = spawn_tls_app(pool, &files, None).await;
+
+    let response = certificate
+        .client()
+        .build()
+        .unwrap()
+        .get(format!("{}/health/live", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.version(), Version::HTTP_2);
+
+    let response = certificate
+        .client()
+        .http1_only()
+        .build()
+        .unwrap()
+        .get(format!("{}/health/live", address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.version(), Version::HTTP_11);
+}
+
+#[sqlx::test]
+async fn replaced_certificate_files_are_picked_up_without_restart(pool: PgPool) {
+    let old_certificate = TestCertificate::generate();
+    let new_certificate = TestCertificate::generate();
+    let files = CertificateFiles::new(&old_certificate);
+    let (_app, address) = spawn_tls_app(pool, &files, None).await;
+    let url = format!("{}/health/live", address);
+
+    // Каждый клиент доверяет только своему сертификату.
+    let old_client = old_certificate.client().build().unwrap();
+    let new_client = new_certificate.client().build().unwrap();
+    assert!(old_client.get(&url).send().await.is_ok());
+    assert!(new_client.get(&url).send().await.is_err());
+
+    files.replace(&new_certificate);
+
+    let mut reloaded = false;
+    for _ in 0..50 {
+        if new_client.get(&url).send().await.is_ok() {
+            reloaded = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(reloaded, "Replaced certificate was not picked up.");
+
+    let fresh_old_client = old_certificate.client().build().unwrap();
+    assert!(fresh_old_client.get(&url).send().await.is_err());
+}
+
+#[sqlx::test]
+async fn http_listener_redirects_to_https(pool: PgPool) {
+    let certificate = TestCertificate::generate();
+    let files = CertificateFiles::new(&certificate);
+    let redirect_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let (app, address) = spawn_tls_app(pool, &files, Some(redirect_port)).await;
+
+    let response = app
+        .client
+        .get(format!(
+            "http://127.0.0.1:{}/users/register/confirm/?token=abc",
+            redirect_port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 308);
+    assert_eq!(
+        response.headers()[LOCATION],
+        format!("{}/users/register/confirm/?token=abc", address).as_str()
+    );
+}
@@ -0,0 +1,566 @@
+use crate::helpers::{
+    confirmation_link, confirmation_token, session_cookie, spawn_app, spawn_app_with, unique_email,
+    wait_for_email, wait_for_email_where, TestApp, PASSWORD,
+};
+use backend::settings::get_settings;
+use backend::utils::{hash, renormalize_emails};
+use reqwest::header::LOCATION;
+use sqlx::{PgPool, Row};
+
+#[sqlx::test]
+async fn register_confirm_login_logout_journey(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+
+    let sent = wait_for_email(&email).await;
+    assert!(confirmation_link(&sent).contains("/users/register/confirm/?token="));
+
+    let response = app.get_confirm(&confirmation_token(&sent)).await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert!(response.headers()[LOCATION]
+        .to_str()
+        .unwrap()
+        .ends_with("/auth/confirmed"));
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    let cookie = session_cookie(&response).expect("Login did not set a session cookie.");
+    let user: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(user["email"], email);
+    assert_eq!(user["is_active"], true);
+
+    let (csrf_token, cookie) = app.get_csrf_token(&cookie).await;
+    let response = app.post_logout(Some(&cookie), Some(&csrf_token)).await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn registration_stores_an_inactive_user(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+
+    let row = sqlx::query("SELECT is_active FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Registered user is missing.");
+    assert!(!row.get::<bool, _>("is_active"));
+}
+
+#[sqlx::test]
+async fn login_fails_before_activation(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert!(!response.status().is_success());
+    assert!(session_cookie(&response).is_none());
+}
+
+#[sqlx::test]
+async fn login_fails_with_wrong_password(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+    let token = confirmation_token(&wait_for_email(&email).await);
+    app.get_confirm(&token).await;
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "not-the-password" }))
+        .await;
+    assert!(!response.status().is_success());
+}
+
+#[sqlx::test]
+async fn failed_logins_are_indistinguishable_with_anti_enumeration(pool: PgPool) {
+    let app = spawn_app_with(pool, |settings| settings.security.anti_enumeration = true).await;
+
+    let active = app.register_new_user().await;
+    let token = confirmation_token(&wait_for_email(&active).await);
+    app.get_confirm(&token).await;
+    let inactive = app.register_new_user().await;
+
+    let mut responses = Vec::new();
+    for (email, password) in [
+        (unique_email(), PASSWORD),
+        (inactive, PASSWORD),
+        (active, "not-the-password"),
+    ] {
+        let response = app
+            .post_login(&serde_json::json!({ "email": email, "password": password }))
+            .await;
+        let status = response.status().as_u16();
+        let mut body: serde_json::Value = response.json().await.unwrap();
+        // Идентификатор запроса у каждого ответа свой.
+        body.as_object_mut().unwrap().remove("request_id");
+        responses.push((status, body));
+    }
+
+    assert_eq!(responses[0].0, 400);
+    assert!(
+        responses.iter().all(|response| response == &responses[0]),
+        "{:?}",
+        responses
+    );
+}
+
+#[sqlx::test]
+async fn confirmation_token_cannot_be_reused(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+    let token = confirmation_token(&wait_for_email(&email).await);
+
+    let first = app.get_confirm(&token).await;
+    assert!(first.headers()[LOCATION]
+        .to_str()
+        .unwrap()
+        .ends_with("/auth/confirmed"));
+
+    let second = app.get_confirm(&token).await;
+    assert_eq!(second.status().as_u16(), 303);
+    let location = url::Url::parse(second.headers()[LOCATION].to_str().unwrap()).unwrap();
+    assert_eq!(location.path(), "/auth/regenerate-token");
+    assert!(location
+        .query_pairs()
+        .any(|(key, value)| key == "reason" && value == "invalid_token"));
+}
+
+#[sqlx::test]
+async fn duplicate_registration_is_rejected(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+
+    let response = app
+        .post_register(&serde_json::json!({
+            "email": email.to_uppercase(),
+            "password": PASSWORD,
+            "first_name": "Test",
+            "last_name": "User",
+        }))
+        .await;
+    assert!(!response.status().is_success());
+}
+
+#[sqlx::test]
+async fn duplicate_registration_looks_successful_with_anti_enumeration(pool: PgPool) {
+    let app = spawn_app_with(pool, |settings| settings.security.anti_enumeration = true).await;
+
+    let email = unique_email();
+    let registration = serde_json::json!({
+        "email": email,
+        "password": PASSWORD,
+        "first_name": "Test",
+        "last_name": "User",
+    });
+    let first = app.post_register(&registration).await;
+    assert_eq!(first.status().as_u16(), 200);
+    let first_body = first.text().await.unwrap();
+    let token = confirmation_token(&wait_for_email(&email).await);
+    app.get_confirm(&token).await;
+
+    let mut duplicate = registration.clone();
+    duplicate["email"] = serde_json::json!(email.to_uppercase());
+    let second = app.post_register(&duplicate).await;
+    assert_eq!(second.status().as_u16(), 200);
+    assert_eq!(second.text().await.unwrap(), first_body);
+
+    // Письмо уходит владельцу учётной записи, на сохранённое написание адреса.
+    let notice = wait_for_email_where(&email, |message| {
+        message.subject.contains("You already have an account")
+    })
+    .await;
+    assert!(!notice.text.contains("/users/register/confirm/"));
+}
+
+#[sqlx::test]
+async fn register_rejects_invalid_payload(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .post_register(&serde_json::json!({
+            "email": "not-an-email",
+            "password": PASSWORD,
+            "first_name": "Test",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["fields"].get("last_name").is_some());
+
+    let response = app
+        .post_register(&serde_json::json!({
+            "email": unique_email(),
+            "password": "short",
+            "first_name": "Test",
+            "last_name": "User",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["fields"]["password"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|error| error["code"] == "password_too_short"));
+}
+
+#[sqlx::test]
+async fn logout_without_session_is_rejected(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app.post_logout(None, None).await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+/// Вход для тестов CSRF: возвращает cookie сессии активного пользователя.
+async fn logged_in_cookie(app: &TestApp) -> String {
+    let email = app.register_new_user().await;
+    let sent = wait_for_email(&email).await;
+    app.get_confirm(&confirmation_token(&sent)).await;
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    session_cookie(&response).expect("Login did not set a session cookie.")
+}
+
+#[sqlx::test]
+async fn logout_without_csrf_token_is_forbidden(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let cookie = logged_in_cookie(&app).await;
+
+    let response = app.post_logout(Some(&cookie), None).await;
+    assert_eq!(response.status().as_u16(), 403);
+
+    let (_, cookie) = app.get_csrf_token(&cookie).await;
+    let response = app.post_logout(Some(&cookie), Some("forged")).await;
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn bearer_clients_are_exempt_from_csrf(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let cookie = logged_in_cookie(&app).await;
+
+    let response = app
+        .client
+        .post(format!("{}/users/logout/", app.address))
+        .header(reqwest::header::COOKIE, &cookie)
+        .header(reqwest::header::AUTHORIZATION, "Bearer some-token")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_ne!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn confirmation_returns_json_when_asked(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = app.register_new_user().await;
+    let token = confirmation_token(&wait_for_email(&email).await);
+
+    let confirm = |token: String| {
+        app.client
+            .get(format!("{}/users/register/confirm/", app.address))
+            .query(&[("token", token)])
+            .header(reqwest::header::ACCEPT, "application/json")
+            .send()
+    };
+
+    let response = confirm(token.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["next"].is_null());
+
+    // Токен одноразовый
+    let response = confirm(token).await.unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "invalid_token");
+}
+
+#[sqlx::test]
+async fn confirmation_redirects_to_next_from_the_token(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let email = unique_email();
+    let next = "https://admin.localhost:3001/welcome";
+    let response = app
+        .post_register(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "first_name": "Test",
+            "last_name": "User",
+            "next": next,
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let response = app
+        .get_confirm(&confirmation_token(&wait_for_email(&email).await))
+        .await;
+    assert_eq!(response.status().as_u16(), 303);
+    assert_eq!(response.headers()[LOCATION], next);
+}
+
+#[sqlx::test]
+async fn registration_rejects_foreign_next(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .post_register(&serde_json::json!({
+            "email": unique_email(),
+            "password": PASSWORD,
+            "first_name": "Test",
+            "last_name": "User",
+            "next": "https://evil.example.com/phish",
+        }))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["fields"]["next"][0]["code"], "redirect_not_allowed");
+}
+
+#[sqlx::test]
+async fn validation_errors_follow_accept_language(pool: PgPool) {
+    let app = spawn_app(pool).await;
+
+    let response = app
+        .client
+        .post(format!("{}/users/register/", app.address))
+        .header("Accept-Language", "de;q=0.9, ru;q=0.8, en;q=0.5")
+        .json(&serde_json::json!({
+            "email": "not-an-email",
+            "password": PASSWORD,
+            "first_name": "Test",
+            "last_name": "User",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 400);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Некорректный запрос.");
+    assert_eq!(body["fields"]["email"][0]["code"], "email");
+    assert_eq!(
+        body["fields"]["email"][0]["message"],
+        "Введите корректный адрес электронной почты."
+    );
+}
+
+#[sqlx::test]
+async fn registration_language_is_stored_and_used_for_email(pool: PgPool) {
+    let app = spawn_app(pool).await;
+    let email = unique_email();
+
+    let response = app
+        .client
+        .post(format!("{}/users/register/", app.address))
+        .header("Accept-Language", "ru-RU")
+        .json(&serde_json::json!({
+            "email": email,
+            "password": PASSWORD,
+            "first_name": "Test",
+            "last_name": "User",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    let row = sqlx::query("SELECT locale FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&app.pool)
+        .await
+        .expect("Registered user is missing.");
+    assert_eq!(
+        row.get::<Option<String>, _>("locale").as_deref(),
+        Some("ru")
+    );
+
+    let sent = wait_for_email(&email).await;
+    assert_eq!(
+        sent.subject,
+        "RustAuth — подтвердите адрес электронной почты"
+    );
+    assert!(sent.html.contains("lang=\"ru\""));
+}
+
+#[sqlx::test]
+async fn audit_log_ignores_forwarded_for_from_untrusted_peers(pool: PgPool) {
+    let app = spawn_app(pool.clone()).await;
+
+    let response = app
+        .client
+        .post(format!("{}/users/login/", app.address))
+        .header("X-Forwarded-For", "203.0.113.7")
+        .json(&serde_json::json!({ "email": unique_email(), "password": PASSWORD }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert!(!response.status().is_success());
+
+    let row = sqlx::query("SELECT ip_address FROM auth_events WHERE event_type = 'login'")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(row.get::<String, _>("ip_address"), "127.0.0.1");
+}
+
+#[sqlx::test]
+async fn revoked_staff_rights_apply_to_existing_sessions(pool: PgPool) {
+    let app = spawn_app(pool.clone()).await;
+
+    let email = app.register_new_user().await;
+    let sent = wait_for_email(&email).await;
+    app.get_confirm(&confirmation_token(&sent)).await;
+    sqlx::query("UPDATE users SET is_staff = TRUE WHERE email = $1")
+        .bind(&email)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    let cookie = session_cookie(&response).expect("Login did not set a session cookie.");
+
+    let admin_events = || {
+        app.client
+            .get(format!("{}/users/admin/events/", app.address))
+            .header(reqwest::header::COOKIE, &cookie)
+            .send()
+    };
+    let response = admin_events().await.expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 200);
+
+    sqlx::query("UPDATE users SET is_staff = FALSE WHERE email = $1")
+        .bind(&email)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = admin_events().await.expect("Failed to execute request.");
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn legacy_password_hash_is_accepted_and_upgraded_to_argon2(pool: PgPool) {
+    let app = spawn_app(pool.clone()).await;
+
+    // Хэш Django `make_password("lètmein", "seasalt", "pbkdf2_sha256")`.
+    let email = unique_email();
+    sqlx::query(
+        "INSERT INTO users (email, email_normalized, password, first_name, last_name, is_active) \
+        VALUES ($1, $1, $2, 'Imported', 'User', TRUE)",
+    )
+    .bind(&email)
+    .bind("pbkdf2_sha256$10000$seasalt$CWWFdHOWwPnki7HvkcqN9iA2T3KLW1cf2uZ5kvArtVY=")
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "lètmein" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let row = sqlx::query("SELECT password FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let password: String = row.get("password");
+    assert!(password.starts_with("$argon2id$"), "{}", password);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": "lètmein" }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn weaker_argon2_hash_is_upgraded_on_login(pool: PgPool) {
+    let app = spawn_app_with(pool.clone(), |settings| {
+        settings.password_hashing.m_cost = 4096;
+        settings.password_hashing.t_cost = 2;
+    })
+    .await;
+
+    // Хэш, посчитанный до повышения стоимости в настройках.
+    let mut weaker = get_settings()
+        .expect("Failed to read settings.")
+        .password_hashing;
+    weaker.m_cost = 1024;
+    weaker.t_cost = 1;
+    let email = unique_email();
+    sqlx::query(
+        "INSERT INTO users (email, email_normalized, password, first_name, last_name, is_active) \
+        VALUES ($1, $1, $2, 'Old', 'Hash', TRUE)",
+    )
+    .bind(&email)
+    .bind(hash(PASSWORD.as_bytes(), &weaker).await)
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let row = sqlx::query("SELECT password FROM users WHERE email = $1")
+        .bind(&email)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let password: String = row.get("password");
+    assert!(password.contains("m=4096,t=2,"), "{}", password);
+
+    let response = app
+        .post_login(&serde_json::json!({ "email": email, "password": PASSWORD }))
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn emails_backfilled_by_migration_are_renormalized_by_admin_command(pool: PgPool) {
+    // Так столбец заполнила миграция: `lower(trim(email))`, без правил Gmail.
+    let local_part = format!("John.Smith.{}", uuid::Uuid::new_v4().simple());
+    let email = format!("{}@googlemail.com", local_part);
+    sqlx::query(
+        "INSERT INTO users (email, email_normalized, password, first_name, last_name, is_active) \
+        VALUES ($1, lower($1), $2, 'John', 'Smith', TRUE)",
+    )
+    .bind(&email)
+    .bind("pbkdf2_sha256$10000$seasalt$CWWFdHOWwPnki7HvkcqN9iA2T3KLW1cf2uZ5kvArtVY=")
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let app = spawn_app(pool.clone()).await;
+    let login_email = format!("{}@gmail.com", local_part.replace('.', ""));
+    let credentials = serde_json::json!({ "email": login_email, "password": "lètmein" });
+
+    // Запуск приложения ключи не пересчитывает
+    let response = app.post_login(&credentials).await;
+    assert!(!response.status().is_success());
+
+    // То же, что `backend-admin normalize-emails`
+    let settings = get_settings().expect("Failed to read settings.");
+    let report = renormalize_emails(&pool, &settings.email_normalization, false)
+        .await
+        .unwrap();
+    assert_eq!(report.updated, 1);
+    assert!(report.conflicts.is_empty());
+
+    let response = app.post_login(&credentials).await;
+    assert_eq!(response.status().as_u16(), 200);
+}